@@ -6,9 +6,19 @@
 //! ```
 
 use serde::{Serialize, Deserialize};
+use ethabi::param_type::{Reader, Writer};
+use ethabi::token::{LenientTokenizer, Tokenizer};
+use tiny_keccak::keccak256;
+use rustc_hex::ToHex;
+use ethereum_types::H256;
 use std::str::FromStr;
 use std::fmt;
 
+pub use ethabi::{ParamType, Token};
+
+pub mod human;
+pub mod diff;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Abi {
@@ -16,6 +26,7 @@ pub enum Abi {
     Constructor(Constructor),
     Fallback(Fallback),
     Event(Event),
+    Error(Error),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -53,24 +64,74 @@ pub struct Event {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    pub r#type: String,
+    pub name: String,
+    pub inputs: Vec<Variable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(non_snake_case)]
 pub struct Variable {
     pub name: String,
     pub r#type: String,
     pub components: Option<Vec<Variable>>,
+    /// Solidity type before ABI encoding, e.g. `struct Ballot.Voter` for a `tuple`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internalType: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(non_snake_case)]
 pub struct EventVariable {
     pub name: String,
     pub r#type: String,
     pub components: Option<Vec<EventVariable>>,
     pub indexed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internalType: Option<String>,
+}
+
+/// Raw log as emitted by the EVM
+#[derive(Debug, Clone, PartialEq)]
+pub struct Log {
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedEvent {
+    pub name: String,
+    pub params: Vec<DecodedParam>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedParam {
+    pub name: String,
+    pub indexed: bool,
+    pub value: DecodedValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedValue {
+    Token(Token),
+    /// Indexed strings, bytes, arrays and structs only keep the keccak256 of their value
+    Hashed(H256),
 }
 
 impl Abi {
     pub fn from_json_array(s: &str) -> Result<Vec<Self>, serde_json::error::Error> {
         serde_json::from_str(s)
     }
+
+    /// Parses an item of the human-readable ABI, e.g. `function owner() view returns (address)`
+    pub fn from_human(s: &str) -> ethabi::Result<Self> {
+        human::parse(s)
+    }
+
+    pub fn to_human(&self) -> String {
+        human::format(self)
+    }
 }
 
 impl fmt::Display for Abi {
@@ -87,10 +148,330 @@ impl FromStr for Abi {
     }
 }
 
+impl Function {
+    /// Canonical signature used to compute the selector, e.g. `transfer(address,uint256)`
+    pub fn signature(&self) -> ethabi::Result<String> {
+        Ok(signature(&self.name, &param_types(&self.inputs)?))
+    }
+
+    /// First four bytes of the keccak256 of the signature
+    pub fn selector(&self) -> ethabi::Result<[u8; 4]> {
+        Ok(selector(&self.signature()?))
+    }
+
+    /// Encodes the calldata of a call to this function, selector included
+    pub fn encode_input(&self, tokens: &[Token]) -> ethabi::Result<Vec<u8>> {
+        let types = param_types(&self.inputs)?;
+        if !Token::types_check(tokens, &types) {
+            return Err(ethabi::Error::InvalidData);
+        }
+
+        let mut calldata = self.selector()?.to_vec();
+        calldata.extend(ethabi::encode(tokens));
+        Ok(calldata)
+    }
+
+    /// Decodes the calldata of a call to this function, selector included
+    pub fn decode_input(&self, calldata: &[u8]) -> ethabi::Result<Vec<Token>> {
+        if calldata.len() < 4 || calldata[..4] != self.selector()? {
+            return Err(ethabi::Error::InvalidData);
+        }
+
+        ethabi::decode(&param_types(&self.inputs)?, &calldata[4..])
+    }
+
+    /// Decodes the data returned by a call to this function
+    pub fn decode_output(&self, data: &[u8]) -> ethabi::Result<Vec<Token>> {
+        ethabi::decode(&param_types(&self.outputs)?, data)
+    }
+}
+
+impl Constructor {
+    /// Appends the encoded constructor arguments to the creation bytecode
+    pub fn encode(&self, bin: &[u8], tokens: &[Token]) -> ethabi::Result<Vec<u8>> {
+        let types = param_types(&self.inputs)?;
+        if !Token::types_check(tokens, &types) {
+            return Err(ethabi::Error::InvalidData);
+        }
+
+        let mut code = bin.to_vec();
+        code.extend(ethabi::encode(tokens));
+        Ok(code)
+    }
+}
+
+impl Error {
+    /// Canonical signature of the error, e.g. `InsufficientBalance(uint256,uint256)`
+    pub fn signature(&self) -> ethabi::Result<String> {
+        Ok(signature(&self.name, &param_types(&self.inputs)?))
+    }
+
+    /// Selector prepended to the revert data, computed like a function selector
+    pub fn selector(&self) -> ethabi::Result<[u8; 4]> {
+        Ok(selector(&self.signature()?))
+    }
+
+    /// Decodes the revert data of this error, selector included
+    pub fn decode(&self, data: &[u8]) -> ethabi::Result<Vec<Token>> {
+        if data.len() < 4 || data[..4] != self.selector()? {
+            return Err(ethabi::Error::InvalidData);
+        }
+
+        ethabi::decode(&param_types(&self.inputs)?, &data[4..])
+    }
+}
+
+impl Event {
+    /// Canonical signature of the event, e.g. `Transfer(address,address,uint256)`
+    pub fn signature(&self) -> ethabi::Result<String> {
+        let types = self.inputs.iter()
+            .map(EventVariable::param_type)
+            .collect::<ethabi::Result<Vec<ParamType>>>()?;
+        Ok(signature(&self.name, &types))
+    }
+
+    /// Topic 0 of the logs emitted by this event, anonymous events don't emit it
+    pub fn topic(&self) -> ethabi::Result<H256> {
+        Ok(H256::from(keccak256(self.signature()?.as_bytes())))
+    }
+
+    pub fn decode_log(&self, log: &Log) -> ethabi::Result<DecodedEvent> {
+        let topics = if self.anonymous {
+            &log.topics[..]
+        } else {
+            match log.topics.split_first() {
+                Some((topic, topics)) if *topic == self.topic()? => topics,
+                _ => return Err(ethabi::Error::InvalidData),
+            }
+        };
+
+        let indexed = self.inputs.iter().filter(|i| i.indexed).count();
+        if indexed != topics.len() {
+            return Err(ethabi::Error::InvalidData);
+        }
+
+        let mut topics = topics.iter();
+        let mut indexed = Vec::new();
+        let mut data_types = Vec::new();
+        for input in self.inputs.iter() {
+            let param_type = input.param_type()?;
+            if !input.indexed {
+                data_types.push(param_type);
+                continue;
+            }
+
+            let topic = topics.next().expect("Topics were counted above");
+            let value = match param_type {
+                ParamType::Bytes
+                | ParamType::String
+                | ParamType::Array(_)
+                | ParamType::FixedArray(_, _)
+                | ParamType::Tuple(_) => DecodedValue::Hashed(*topic),
+                param_type => DecodedValue::Token(ethabi::decode(&[param_type], topic.as_bytes())?.remove(0)),
+            };
+            indexed.push(value);
+        }
+
+        let mut indexed = indexed.into_iter();
+        let mut data = ethabi::decode(&data_types, &log.data)?.into_iter();
+        let params = self.inputs.iter()
+            .map(|input| DecodedParam {
+                name: input.name.clone(),
+                indexed: input.indexed,
+                value: if input.indexed {
+                    indexed.next()
+                } else {
+                    data.next().map(DecodedValue::Token)
+                }.expect("Values were decoded for every input"),
+            })
+            .collect();
+
+        Ok(DecodedEvent {
+            name: self.name.clone(),
+            params,
+        })
+    }
+
+    /// Like `decode_log`, but also requires the data to be the canonical
+    /// encoding of the decoded values. Used to tell anonymous events apart.
+    pub(crate) fn decode_log_strict(&self, log: &Log) -> ethabi::Result<DecodedEvent> {
+        let decoded = self.decode_log(log)?;
+        let data = decoded.params.iter()
+            .filter(|p| !p.indexed)
+            .filter_map(|p| match &p.value {
+                DecodedValue::Token(token) => Some(token.clone()),
+                DecodedValue::Hashed(_) => None,
+            })
+            .collect::<Vec<Token>>();
+
+        if ethabi::encode(&data) != log.data {
+            return Err(ethabi::Error::InvalidData);
+        }
+        Ok(decoded)
+    }
+}
+
+impl DecodedEvent {
+    pub fn get(&self, name: &str) -> Option<&DecodedValue> {
+        self.params.iter()
+            .find(|p| p.name == name)
+            .map(|p| &p.value)
+    }
+}
+
+impl Variable {
+    pub fn param_type(&self) -> ethabi::Result<ParamType> {
+        let components = match &self.components {
+            Some(components) => Some(param_types(components)?),
+            None => None,
+        };
+        resolve_type(&self.r#type, components)
+    }
+}
+
+impl EventVariable {
+    pub fn param_type(&self) -> ethabi::Result<ParamType> {
+        let components = match &self.components {
+            Some(components) => components.iter()
+                .map(EventVariable::param_type)
+                .collect::<ethabi::Result<Vec<ParamType>>>()
+                .map(Some)?,
+            None => None,
+        };
+        resolve_type(&self.r#type, components)
+    }
+}
+
+pub fn param_types(variables: &[Variable]) -> ethabi::Result<Vec<ParamType>> {
+    variables.iter()
+        .map(Variable::param_type)
+        .collect()
+}
+
+/// Parses the values of the variables as written on the command line, e.g.
+/// `1000`, `0x...` or `[1,2,3]`
+pub fn tokenize(variables: &[Variable], values: &[impl AsRef<str>]) -> ethabi::Result<Vec<Token>> {
+    if variables.len() != values.len() {
+        return Err(ethabi::Error::Other(format!("Expected {} argument(s), got {}", variables.len(), values.len())));
+    }
+    param_types(variables)?.iter()
+        .zip(values.iter())
+        .map(|(param, value)| ArgTokenizer::tokenize(param, value.as_ref()))
+        .collect()
+}
+
+/// Lenient tokenizer that also takes `0x` prefixed addresses, bytes and numbers
+struct ArgTokenizer;
+
+impl Tokenizer for ArgTokenizer {
+    fn tokenize_address(value: &str) -> ethabi::Result<[u8; 20]> {
+        LenientTokenizer::tokenize_address(strip_hex_prefix(value))
+    }
+
+    fn tokenize_string(value: &str) -> ethabi::Result<String> {
+        LenientTokenizer::tokenize_string(value)
+    }
+
+    fn tokenize_bool(value: &str) -> ethabi::Result<bool> {
+        LenientTokenizer::tokenize_bool(value)
+    }
+
+    fn tokenize_bytes(value: &str) -> ethabi::Result<Vec<u8>> {
+        LenientTokenizer::tokenize_bytes(strip_hex_prefix(value))
+    }
+
+    fn tokenize_fixed_bytes(value: &str, len: usize) -> ethabi::Result<Vec<u8>> {
+        LenientTokenizer::tokenize_fixed_bytes(strip_hex_prefix(value), len)
+    }
+
+    fn tokenize_uint(value: &str) -> ethabi::Result<[u8; 32]> {
+        match value.strip_prefix("0x") {
+            Some(hex) => Ok(hex.parse::<ethabi::Uint>().map_err(|_| ethabi::Error::InvalidData)?.into()),
+            None => LenientTokenizer::tokenize_uint(value),
+        }
+    }
+
+    fn tokenize_int(value: &str) -> ethabi::Result<[u8; 32]> {
+        match value.starts_with("0x") {
+            true => Self::tokenize_uint(value),
+            false => LenientTokenizer::tokenize_int(value),
+        }
+    }
+}
+
+fn strip_hex_prefix(value: &str) -> &str {
+    value.strip_prefix("0x").unwrap_or(value)
+}
+
+/// Formats a value the way it's written on the command line, numbers in
+/// decimal and bytes in `0x` prefixed hex
+pub fn format_token(token: &Token) -> String {
+    let join = |tokens: &[Token]| tokens.iter().map(format_token).collect::<Vec<String>>().join(", ");
+    match token {
+        Token::Address(address) => format!("0x{:x}", address),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => format!("0x{}", bytes.to_hex::<String>()),
+        Token::Int(value) if value.bit(255) => format!("-{}", (!*value).overflowing_add(1.into()).0),
+        Token::Int(value) | Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::Array(tokens) | Token::FixedArray(tokens) => format!("[{}]", join(tokens)),
+        Token::Tuple(tokens) => format!("({})", join(tokens)),
+    }
+}
+
+fn signature(name: &str, types: &[ParamType]) -> String {
+    format!("{}({})", name, types.iter().map(Writer::write).collect::<Vec<String>>().join(","))
+}
+
+fn selector(signature: &str) -> [u8; 4] {
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&keccak256(signature.as_bytes())[..4]);
+    selector
+}
+
+/// Solc describes structs as `tuple`, `tuple[]`, `tuple[2][]`... with the
+/// members in `components`, every other type is understood by ethabi's reader
+fn resolve_type(r#type: &str, components: Option<Vec<ParamType>>) -> ethabi::Result<ParamType> {
+    if !r#type.starts_with("tuple") {
+        return Reader::read(r#type);
+    }
+
+    let components = components.ok_or_else(|| ethabi::Error::InvalidName(r#type.to_owned()))?;
+    let mut param_type = ParamType::Tuple(components.into_iter().map(Box::new).collect());
+
+    let mut suffix = &r#type["tuple".len()..];
+    while !suffix.is_empty() {
+        let end = match (suffix.starts_with('['), suffix.find(']')) {
+            (true, Some(end)) => end,
+            _ => return Err(ethabi::Error::InvalidName(r#type.to_owned())),
+        };
+
+        param_type = match &suffix[1..end] {
+            "" => ParamType::Array(Box::new(param_type)),
+            len => ParamType::FixedArray(Box::new(param_type), len.parse()?),
+        };
+        suffix = &suffix[end + 1..];
+    }
+
+    Ok(param_type)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Abi;
-    use std::str::FromStr;
+    use super::*;
+    use ethereum_types::U256;
+    use rustc_hex::FromHex;
+
+    fn function(json: &str) -> Function {
+        match Abi::from_str(json).expect("Couldn't parse the input") {
+            Abi::Function(func) => func,
+            _ => unreachable!(),
+        }
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        s.split_whitespace().collect::<String>().from_hex().unwrap()
+    }
 
     #[test]
     fn test_function_one_input_no_output_from_str() {
@@ -296,4 +677,434 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_encode_static_input() {
+        let baz = function(r#"{
+            "inputs": [
+              { "name": "x", "type": "uint32" },
+              { "name": "y", "type": "bool" }
+            ],
+            "name": "baz",
+            "outputs": [ { "name": "r", "type": "bool" } ],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        assert_eq!(baz.signature().unwrap(), "baz(uint32,bool)");
+        assert_eq!(baz.selector().unwrap(), [0xcd, 0xcd, 0x77, 0xc0]);
+
+        let tokens = vec![Token::Uint(U256::from(69)), Token::Bool(true)];
+        let calldata = baz.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            cdcd77c0
+            0000000000000000000000000000000000000000000000000000000000000045
+            0000000000000000000000000000000000000000000000000000000000000001"));
+        assert_eq!(baz.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_fixed_array_input() {
+        let bar = function(r#"{
+            "inputs": [ { "name": "x", "type": "bytes3[2]" } ],
+            "name": "bar",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        let tokens = vec![Token::FixedArray(vec![
+            Token::FixedBytes(b"abc".to_vec()),
+            Token::FixedBytes(b"def".to_vec()),
+        ])];
+        let calldata = bar.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            fce353f6
+            6162630000000000000000000000000000000000000000000000000000000000
+            6465660000000000000000000000000000000000000000000000000000000000"));
+        assert_eq!(bar.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_dynamic_input() {
+        let sam = function(r#"{
+            "inputs": [
+              { "name": "a", "type": "bytes" },
+              { "name": "b", "type": "bool" },
+              { "name": "c", "type": "uint256[]" }
+            ],
+            "name": "sam",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        let tokens = vec![
+            Token::Bytes(b"dave".to_vec()),
+            Token::Bool(true),
+            Token::Array(vec![
+                Token::Uint(U256::from(1)),
+                Token::Uint(U256::from(2)),
+                Token::Uint(U256::from(3)),
+            ]),
+        ];
+        let calldata = sam.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            a5643bf2
+            0000000000000000000000000000000000000000000000000000000000000060
+            0000000000000000000000000000000000000000000000000000000000000001
+            00000000000000000000000000000000000000000000000000000000000000a0
+            0000000000000000000000000000000000000000000000000000000000000004
+            6461766500000000000000000000000000000000000000000000000000000000
+            0000000000000000000000000000000000000000000000000000000000000003
+            0000000000000000000000000000000000000000000000000000000000000001
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000003"));
+        assert_eq!(sam.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_mixed_input() {
+        let f = function(r#"{
+            "inputs": [
+              { "name": "a", "type": "uint256" },
+              { "name": "b", "type": "uint32[]" },
+              { "name": "c", "type": "bytes10" },
+              { "name": "d", "type": "bytes" }
+            ],
+            "name": "f",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        let tokens = vec![
+            Token::Uint(U256::from(0x123)),
+            Token::Array(vec![Token::Uint(U256::from(0x456)), Token::Uint(U256::from(0x789))]),
+            Token::FixedBytes(b"1234567890".to_vec()),
+            Token::Bytes(b"Hello, world!".to_vec()),
+        ];
+        let calldata = f.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            8be65246
+            0000000000000000000000000000000000000000000000000000000000000123
+            0000000000000000000000000000000000000000000000000000000000000080
+            3132333435363738393000000000000000000000000000000000000000000000
+            00000000000000000000000000000000000000000000000000000000000000e0
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000456
+            0000000000000000000000000000000000000000000000000000000000000789
+            000000000000000000000000000000000000000000000000000000000000000d
+            48656c6c6f2c20776f726c642100000000000000000000000000000000000000"));
+        assert_eq!(f.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_nested_dynamic_input() {
+        let g = function(r#"{
+            "inputs": [
+              { "name": "a", "type": "uint256[][]" },
+              { "name": "b", "type": "string[]" }
+            ],
+            "name": "g",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        let tokens = vec![
+            Token::Array(vec![
+                Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]),
+                Token::Array(vec![Token::Uint(U256::from(3))]),
+            ]),
+            Token::Array(vec![
+                Token::String("one".to_owned()),
+                Token::String("two".to_owned()),
+                Token::String("three".to_owned()),
+            ]),
+        ];
+        let calldata = g.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            2289b18c
+            0000000000000000000000000000000000000000000000000000000000000040
+            0000000000000000000000000000000000000000000000000000000000000140
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000040
+            00000000000000000000000000000000000000000000000000000000000000a0
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000001
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000001
+            0000000000000000000000000000000000000000000000000000000000000003
+            0000000000000000000000000000000000000000000000000000000000000003
+            0000000000000000000000000000000000000000000000000000000000000060
+            00000000000000000000000000000000000000000000000000000000000000a0
+            00000000000000000000000000000000000000000000000000000000000000e0
+            0000000000000000000000000000000000000000000000000000000000000003
+            6f6e650000000000000000000000000000000000000000000000000000000000
+            0000000000000000000000000000000000000000000000000000000000000003
+            74776f0000000000000000000000000000000000000000000000000000000000
+            0000000000000000000000000000000000000000000000000000000000000005
+            7468726565000000000000000000000000000000000000000000000000000000"));
+        assert_eq!(g.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_tuple_input() {
+        let h = function(r#"{
+            "inputs": [
+              {
+                "name": "s",
+                "type": "tuple",
+                "components": [
+                  { "name": "a", "type": "uint256" },
+                  { "name": "b", "type": "uint256[]" },
+                  {
+                    "name": "c",
+                    "type": "tuple[]",
+                    "components": [
+                      { "name": "x", "type": "uint256" },
+                      { "name": "y", "type": "uint256" }
+                    ]
+                  }
+                ]
+              },
+              {
+                "name": "t",
+                "type": "tuple",
+                "components": [
+                  { "name": "x", "type": "uint256" },
+                  { "name": "y", "type": "uint256" }
+                ]
+              },
+              { "name": "a", "type": "uint256" }
+            ],
+            "name": "f",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        assert_eq!(h.signature().unwrap(), "f((uint256,uint256[],(uint256,uint256)[]),(uint256,uint256),uint256)");
+        assert_eq!(h.selector().unwrap(), [0x6f, 0x2b, 0xe7, 0x28]);
+
+        let point = |x: u64, y: u64| Token::Tuple(vec![Token::Uint(U256::from(x)), Token::Uint(U256::from(y))]);
+        let tokens = vec![
+            Token::Tuple(vec![
+                Token::Uint(U256::from(1)),
+                Token::Array(vec![Token::Uint(U256::from(2)), Token::Uint(U256::from(3))]),
+                Token::Array(vec![point(4, 5), point(6, 7)]),
+            ]),
+            point(8, 9),
+            Token::Uint(U256::from(10)),
+        ];
+        let calldata = h.encode_input(&tokens).unwrap();
+        assert_eq!(calldata, hex("
+            6f2be728
+            0000000000000000000000000000000000000000000000000000000000000080
+            0000000000000000000000000000000000000000000000000000000000000008
+            0000000000000000000000000000000000000000000000000000000000000009
+            000000000000000000000000000000000000000000000000000000000000000a
+            0000000000000000000000000000000000000000000000000000000000000001
+            0000000000000000000000000000000000000000000000000000000000000060
+            00000000000000000000000000000000000000000000000000000000000000c0
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000003
+            0000000000000000000000000000000000000000000000000000000000000002
+            0000000000000000000000000000000000000000000000000000000000000004
+            0000000000000000000000000000000000000000000000000000000000000005
+            0000000000000000000000000000000000000000000000000000000000000006
+            0000000000000000000000000000000000000000000000000000000000000007"));
+        assert_eq!(h.decode_input(&calldata).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_decode_output() {
+        let name = function(r#"{
+            "inputs": [],
+            "name": "name",
+            "outputs": [ { "name": "", "type": "string" } ],
+            "stateMutability": "view",
+            "type": "function"
+        }"#);
+
+        let output = hex("
+            0000000000000000000000000000000000000000000000000000000000000020
+            000000000000000000000000000000000000000000000000000000000000000d
+            48656c6c6f2c20776f726c642100000000000000000000000000000000000000");
+        assert_eq!(name.decode_output(&output).unwrap(), vec![Token::String("Hello, world!".to_owned())]);
+    }
+
+    #[test]
+    fn test_encode_wrong_input() {
+        let baz = function(r#"{
+            "inputs": [ { "name": "x", "type": "uint32" } ],
+            "name": "baz",
+            "outputs": [],
+            "stateMutability": "pure",
+            "type": "function"
+        }"#);
+
+        assert!(baz.encode_input(&[Token::Bool(true)]).is_err());
+        assert!(baz.encode_input(&[]).is_err());
+        assert!(baz.decode_input(&hex("cdcd77c0")).is_err());
+    }
+
+    #[test]
+    fn test_encode_constructor() {
+        let input = r#"{
+            "inputs": [ { "name": "proposalNames", "type": "bytes32[]" } ],
+            "stateMutability": "nonpayable",
+            "type": "constructor"
+        }"#;
+
+        let constructor = match Abi::from_str(input).unwrap() {
+            Abi::Constructor(constructor) => constructor,
+            _ => unreachable!(),
+        };
+
+        let code = constructor.encode(&hex("6080"), &[Token::Array(vec![Token::FixedBytes(vec![0x11; 32])])]).unwrap();
+        assert_eq!(code, hex("
+            6080
+            0000000000000000000000000000000000000000000000000000000000000020
+            0000000000000000000000000000000000000000000000000000000000000001
+            1111111111111111111111111111111111111111111111111111111111111111"));
+    }
+
+    fn event(json: &str) -> Event {
+        match Abi::from_str(json).expect("Couldn't parse the input") {
+            Abi::Event(event) => event,
+            _ => unreachable!(),
+        }
+    }
+
+    fn topic(s: &str) -> H256 {
+        H256::from_slice(&hex(s))
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let transfer = event(r#"{
+            "anonymous": false,
+            "inputs": [
+              { "indexed": true, "name": "from", "type": "address" },
+              { "indexed": true, "name": "to", "type": "address" },
+              { "indexed": false, "name": "value", "type": "uint256" }
+            ],
+            "name": "Transfer",
+            "type": "event"
+        }"#);
+
+        assert_eq!(transfer.signature().unwrap(), "Transfer(address,address,uint256)");
+        assert_eq!(transfer.topic().unwrap(), topic("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+
+        let log = Log {
+            topics: vec![
+                topic("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"),
+                topic("0000000000000000000000001111111111111111111111111111111111111111"),
+                topic("0000000000000000000000002222222222222222222222222222222222222222"),
+            ],
+            data: hex("00000000000000000000000000000000000000000000000000000000000003e8"),
+        };
+
+        let decoded = transfer.decode_log(&log).unwrap();
+        assert_eq!(decoded.name, "Transfer");
+        assert_eq!(decoded.params.len(), 3);
+        assert!(decoded.params[0].indexed);
+        assert_eq!(decoded.get("from"), Some(&DecodedValue::Token(Token::Address([0x11; 20].into()))));
+        assert_eq!(decoded.get("to"), Some(&DecodedValue::Token(Token::Address([0x22; 20].into()))));
+        assert_eq!(decoded.get("value"), Some(&DecodedValue::Token(Token::Uint(U256::from(1000)))));
+
+        let mut wrong_topic = log.clone();
+        wrong_topic.topics[0] = topic("0000000000000000000000000000000000000000000000000000000000000000");
+        assert!(transfer.decode_log(&wrong_topic).is_err());
+
+        let mut missing_topic = log;
+        missing_topic.topics.pop();
+        assert!(transfer.decode_log(&missing_topic).is_err());
+    }
+
+    #[test]
+    fn test_decode_log_indexed_dynamic() {
+        let registered = event(r#"{
+            "anonymous": false,
+            "inputs": [
+              { "indexed": true, "name": "name", "type": "string" },
+              { "indexed": false, "name": "owner", "type": "address" }
+            ],
+            "name": "Registered",
+            "type": "event"
+        }"#);
+
+        let hashed_name = H256::from(keccak256(b"tortilla"));
+        let log = Log {
+            topics: vec![registered.topic().unwrap(), hashed_name],
+            data: hex("0000000000000000000000003333333333333333333333333333333333333333"),
+        };
+
+        let decoded = registered.decode_log(&log).unwrap();
+        assert_eq!(decoded.get("name"), Some(&DecodedValue::Hashed(hashed_name)));
+        assert_eq!(decoded.get("owner"), Some(&DecodedValue::Token(Token::Address([0x33; 20].into()))));
+    }
+
+    #[test]
+    fn test_error() {
+        let input = r#"
+            {
+              "inputs": [
+                { "internalType": "uint256", "name": "available", "type": "uint256" },
+                { "internalType": "uint256", "name": "required", "type": "uint256" }
+              ],
+              "name": "InsufficientBalance",
+              "type": "error"
+            }"#;
+
+        let error = match Abi::from_str(input).expect("Couldn't parse the input") {
+            Abi::Error(error) => error,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(error.name, "InsufficientBalance");
+        assert_eq!(error.inputs.len(), 2);
+        assert_eq!(error.signature().unwrap(), "InsufficientBalance(uint256,uint256)");
+
+        let mut data = error.selector().unwrap().to_vec();
+        data.extend(ethabi::encode(&[Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]));
+        assert_eq!(error.decode(&data).unwrap(), vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let inputs = human::parse_all("function f(uint256 a, address b, uint8[] c)").unwrap();
+        let inputs = match &inputs[0] {
+            Abi::Function(f) => &f.inputs,
+            _ => unreachable!(),
+        };
+
+        let tokens = tokenize(inputs, &["1000", "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab", "[1,2]"]).unwrap();
+        assert_eq!(tokens[0], Token::Uint(U256::from(1000)));
+        assert_eq!(tokens[1], Token::Address("e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap()));
+        assert_eq!(tokens[2], Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]));
+
+        let prefixed = tokenize(inputs, &["0x3e8", "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab", "[1,2]"]).unwrap();
+        assert_eq!(prefixed, tokens);
+
+        assert!(tokenize(inputs, &["1000"]).is_err());
+        assert!(tokenize(inputs, &["x", "0x00", "[]"]).is_err());
+    }
+
+    #[test]
+    fn test_format_token() {
+        let address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+        assert_eq!(format_token(&Token::Address(address)), "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab");
+        assert_eq!(format_token(&Token::Uint(U256::from(1000))), "1000");
+        assert_eq!(format_token(&Token::Int(U256::MAX)), "-1");
+        assert_eq!(format_token(&Token::Bytes(vec![0xca, 0xfe])), "0xcafe");
+        assert_eq!(format_token(&Token::String("Hello".to_owned())), r#""Hello""#);
+        assert_eq!(
+            format_token(&Token::Tuple(vec![Token::Bool(true), Token::Array(vec![Token::Uint(U256::one())])])),
+            "(true, [1])"
+        );
+    }
 }
@@ -0,0 +1,340 @@
+//! Compatibility check between two versions of an ABI, from the point of view
+//! of the clients calling the old one.
+
+use super::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Human-readable form of the item, from the new ABI unless it was removed
+    pub item: String,
+    pub description: String,
+    pub breaking: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AbiDiff {
+    pub changes: Vec<Change>,
+}
+
+impl AbiDiff {
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+
+    fn push(&mut self, kind: ChangeKind, item: &Abi, description: impl Into<String>, breaking: bool) {
+        self.changes.push(Change {
+            kind,
+            item: item.to_human(),
+            description: description.into(),
+            breaking,
+        });
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        write!(f, "{} {}: {}", sign, self.item, self.description)?;
+        if self.breaking {
+            write!(f, " [breaking]")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AbiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Key used to pair the items of both versions, items without a name
+/// (constructor, fallback and receive) are paired by their type
+fn key(abi: &Abi) -> String {
+    let signature = match abi {
+        Abi::Function(f) => f.signature(),
+        Abi::Event(e) => e.signature(),
+        Abi::Error(e) => e.signature(),
+        Abi::Constructor(c) => Ok(c.r#type.clone()),
+        Abi::Fallback(f) => Ok(f.r#type.clone()),
+    };
+    format!("{} {}", kind(abi), signature.unwrap_or_else(|_| abi.to_human()))
+}
+
+fn kind(abi: &Abi) -> &'static str {
+    match abi {
+        Abi::Function(_) => "function",
+        Abi::Event(_) => "event",
+        Abi::Error(_) => "error",
+        Abi::Constructor(_) => "constructor",
+        Abi::Fallback(_) => "fallback",
+    }
+}
+
+fn name(abi: &Abi) -> Option<&str> {
+    match abi {
+        Abi::Function(f) => Some(&f.name),
+        Abi::Event(e) => Some(&e.name),
+        Abi::Error(e) => Some(&e.name),
+        Abi::Constructor(_) | Abi::Fallback(_) => None,
+    }
+}
+
+pub fn diff(old: &[Abi], new: &[Abi]) -> AbiDiff {
+    let mut diff = AbiDiff::default();
+
+    let new_keys = new.iter().map(|a| (key(a), a)).collect::<HashMap<String, &Abi>>();
+    let old_keys = old.iter().map(|a| (key(a), a)).collect::<HashMap<String, &Abi>>();
+
+    // Items of the new version not present in the old one, the ones sharing
+    // a name with a removed item are reported as a change of that item
+    let mut unmatched = new.iter()
+        .filter(|a| !old_keys.contains_key(&key(a)))
+        .collect::<Vec<&Abi>>();
+
+    for old_item in old.iter() {
+        if let Some(new_item) = new_keys.get(&key(old_item)) {
+            compare(&mut diff, old_item, new_item);
+            continue;
+        }
+
+        let changed = unmatched.iter()
+            .position(|n| kind(n) == kind(old_item) && name(n).is_some() && name(n) == name(old_item));
+
+        match changed {
+            Some(i) => {
+                let new_item = unmatched.remove(i);
+                let description = format!("parameter types changed from `{}`", old_item.to_human());
+                let breaking = !matches!(old_item, Abi::Constructor(_));
+                diff.push(ChangeKind::Changed, new_item, description, breaking);
+            },
+            None => {
+                let breaking = match old_item {
+                    Abi::Function(_) | Abi::Event(_) | Abi::Fallback(_) => true,
+                    Abi::Error(_) | Abi::Constructor(_) => false,
+                };
+                diff.push(ChangeKind::Removed, old_item, "removed", breaking);
+            },
+        }
+    }
+
+    for new_item in unmatched {
+        diff.push(ChangeKind::Added, new_item, "added", false);
+    }
+
+    diff
+}
+
+fn compare(diff: &mut AbiDiff, old: &Abi, new: &Abi) {
+    match (old, new) {
+        (Abi::Function(old_f), Abi::Function(new_f)) => {
+            if types(&old_f.outputs) != types(&new_f.outputs) {
+                let description = format!("return types changed from ({})", types(&old_f.outputs).join(","));
+                diff.push(ChangeKind::Changed, new, description, true);
+            }
+
+            if old_f.stateMutability != new_f.stateMutability {
+                let description = format!("state mutability changed from {} to {}", old_f.stateMutability, new_f.stateMutability);
+                let breaking = mutability_breaks(&old_f.stateMutability, &new_f.stateMutability);
+                diff.push(ChangeKind::Changed, new, description, breaking);
+            }
+
+            if names(&old_f.inputs) != names(&new_f.inputs) || names(&old_f.outputs) != names(&new_f.outputs) {
+                diff.push(ChangeKind::Changed, new, "parameter names changed", false);
+            }
+        },
+        (Abi::Event(old_e), Abi::Event(new_e)) => {
+            let indexed = |e: &Event| e.inputs.iter().map(|i| i.indexed).collect::<Vec<bool>>();
+            if indexed(old_e) != indexed(new_e) {
+                let description = format!("indexed parameters changed from `{}`", old.to_human());
+                diff.push(ChangeKind::Changed, new, description, true);
+            }
+
+            if old_e.anonymous != new_e.anonymous {
+                diff.push(ChangeKind::Changed, new, "anonymous changed", true);
+            }
+
+            let input_names = |e: &Event| e.inputs.iter().map(|i| i.name.clone()).collect::<Vec<String>>();
+            if input_names(old_e) != input_names(new_e) {
+                diff.push(ChangeKind::Changed, new, "parameter names changed", false);
+            }
+        },
+        (Abi::Error(old_e), Abi::Error(new_e)) => {
+            if names(&old_e.inputs) != names(&new_e.inputs) {
+                diff.push(ChangeKind::Changed, new, "parameter names changed", false);
+            }
+        },
+        (Abi::Constructor(old_c), Abi::Constructor(new_c)) => {
+            if types(&old_c.inputs) != types(&new_c.inputs) {
+                let description = format!("parameter types changed from `{}`", old.to_human());
+                diff.push(ChangeKind::Changed, new, description, false);
+            }
+        },
+        (Abi::Fallback(old_f), Abi::Fallback(new_f)) => {
+            if old_f.stateMutability != new_f.stateMutability {
+                let description = format!("state mutability changed from {} to {}", old_f.stateMutability, new_f.stateMutability);
+                let breaking = mutability_breaks(&old_f.stateMutability, &new_f.stateMutability);
+                diff.push(ChangeKind::Changed, new, description, breaking);
+            }
+        },
+        _ => unreachable!("Items are paired by kind"),
+    }
+}
+
+/// A client breaks when a function it calls without a transaction starts
+/// writing state, or when it can no longer send value along with a call
+fn mutability_breaks(old: &str, new: &str) -> bool {
+    let read_only = |m: &str| m == "view" || m == "pure";
+    (read_only(old) && !read_only(new)) || (old == "payable" && new != "payable")
+}
+
+fn types(variables: &[Variable]) -> Vec<String> {
+    variables.iter()
+        .map(|v| v.param_type().map(|t| Writer::write(&t)).unwrap_or_else(|_| v.r#type.clone()))
+        .collect()
+}
+
+fn names(variables: &[Variable]) -> Vec<&str> {
+    variables.iter().map(|v| v.name.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abi(lines: &[&str]) -> Vec<Abi> {
+        human::parse_all(&lines.join("\n")).unwrap()
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let old = abi(&[
+            "function owner() view returns (address)",
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+        ]);
+
+        let diff = diff(&old, &old);
+        assert!(diff.is_empty());
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        let old = abi(&[
+            "function owner() view returns (address)",
+            "function kill()",
+            "error Unauthorized()",
+        ]);
+        let new = abi(&[
+            "function owner() view returns (address)",
+            "function pause()",
+            "event Paused(address account)",
+        ]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 4);
+        assert!(diff.is_breaking());
+
+        let breaking = diff.breaking_changes().collect::<Vec<&Change>>();
+        assert_eq!(breaking.len(), 1);
+        assert_eq!(breaking[0].kind, ChangeKind::Removed);
+        assert_eq!(breaking[0].item, "function kill()");
+
+        assert!(diff.changes.iter().any(|c| c.kind == ChangeKind::Removed && c.item == "error Unauthorized()" && !c.breaking));
+        assert!(diff.changes.iter().any(|c| c.kind == ChangeKind::Added && c.item == "function pause()"));
+        assert!(diff.changes.iter().any(|c| c.kind == ChangeKind::Added && c.item == "event Paused(address account)"));
+    }
+
+    #[test]
+    fn test_changed_types() {
+        let old = abi(&[
+            "function setCompleted(uint256 completed)",
+            "function balanceOf(address owner) view returns (uint256)",
+        ]);
+        let new = abi(&[
+            "function setCompleted(uint64 completed)",
+            "function balanceOf(address owner) view returns (uint128)",
+        ]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 2);
+        assert!(diff.changes.iter().all(|c| c.kind == ChangeKind::Changed && c.breaking));
+        assert_eq!(diff.changes[0].item, "function setCompleted(uint64 completed)");
+    }
+
+    #[test]
+    fn test_changed_mutability() {
+        let old = abi(&[
+            "function a() view returns (uint256)",
+            "function b()",
+            "function c() payable",
+            "function d() pure returns (uint256)",
+        ]);
+        let new = abi(&[
+            "function a() returns (uint256)",
+            "function b() view",
+            "function c()",
+            "function d() view returns (uint256)",
+        ]);
+
+        let diff = diff(&old, &new);
+        let breaking = diff.changes.iter()
+            .map(|c| (c.item.as_str(), c.breaking))
+            .collect::<Vec<(&str, bool)>>();
+
+        assert_eq!(breaking, vec![
+            ("function a() returns (uint256)", true),
+            ("function b() view", false),
+            ("function c()", true),
+            ("function d() view returns (uint256)", false),
+        ]);
+        assert!(diff.changes[0].to_string().starts_with("~ function a() returns (uint256): "));
+        assert!(diff.changes[0].to_string().ends_with(" [breaking]"));
+    }
+
+    #[test]
+    fn test_changed_event_indexing() {
+        let old = abi(&["event Transfer(address indexed from, address indexed to, uint256 value)"]);
+        let new = abi(&["event Transfer(address indexed from, address to, uint256 value)"]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert!(diff.is_breaking());
+        assert_eq!(diff.changes[0].kind, ChangeKind::Changed);
+    }
+
+    #[test]
+    fn test_renamed_parameters() {
+        let old = abi(&["function upgrade(address new_address)"]);
+        let new = abi(&["function upgrade(address newAddress)"]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert!(!diff.is_breaking());
+        assert_eq!(diff.to_string(), "~ function upgrade(address newAddress): parameter names changed\n");
+    }
+}
@@ -0,0 +1,475 @@
+//! Human-readable ABI, the compact format popularized by ethers.js:
+//!
+//! ```text
+//! function transfer(address to, uint256 amount) returns (bool)
+//! event Transfer(address indexed from, address indexed to, uint256 value)
+//! ```
+
+use super::*;
+use std::iter::Peekable;
+use std::str::Chars;
+
+pub fn format(abi: &Abi) -> String {
+    match abi {
+        Abi::Function(f) => {
+            let mut output = format!("function {}({})", f.name, format_variables(&f.inputs));
+            if f.stateMutability != "nonpayable" {
+                output.push(' ');
+                output.push_str(&f.stateMutability);
+            }
+            if !f.outputs.is_empty() {
+                output.push_str(&format!(" returns ({})", format_variables(&f.outputs)));
+            }
+            output
+        },
+        Abi::Constructor(c) => {
+            let mut output = format!("constructor({})", format_variables(&c.inputs));
+            if c.stateMutability == "payable" {
+                output.push_str(" payable");
+            }
+            output
+        },
+        Abi::Fallback(f) => {
+            let mut output = format!("{}() external", f.r#type);
+            if f.stateMutability == "payable" {
+                output.push_str(" payable");
+            }
+            output
+        },
+        Abi::Event(e) => {
+            let inputs = e.inputs.iter()
+                .map(format_event_variable)
+                .collect::<Vec<String>>()
+                .join(", ");
+            let mut output = format!("event {}({})", e.name, inputs);
+            if e.anonymous {
+                output.push_str(" anonymous");
+            }
+            output
+        },
+        Abi::Error(e) => format!("error {}({})", e.name, format_variables(&e.inputs)),
+    }
+}
+
+pub fn format_all(abis: &[Abi]) -> Vec<String> {
+    abis.iter().map(format).collect()
+}
+
+fn format_variables(variables: &[Variable]) -> String {
+    variables.iter()
+        .map(|v| with_name(format_type(&v.r#type, v.components.as_ref().map(|c| format_variables(c))), &v.name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_event_variable(v: &EventVariable) -> String {
+    let components = v.components.as_ref().map(|c| c.iter()
+        .map(format_event_variable)
+        .collect::<Vec<String>>()
+        .join(", "));
+
+    let mut output = format_type(&v.r#type, components);
+    if v.indexed {
+        output.push_str(" indexed");
+    }
+    with_name(output, &v.name)
+}
+
+fn format_type(r#type: &str, components: Option<String>) -> String {
+    match components {
+        Some(components) if r#type.starts_with("tuple") => {
+            format!("tuple({}){}", components, &r#type["tuple".len()..])
+        },
+        _ => r#type.to_owned(),
+    }
+}
+
+fn with_name(r#type: String, name: &str) -> String {
+    if name.is_empty() {
+        r#type
+    } else {
+        format!("{} {}", r#type, name)
+    }
+}
+
+pub fn parse(s: &str) -> ethabi::Result<Abi> {
+    let mut parser = Parser::new(s);
+    let abi = parser.abi()?;
+    parser.end()?;
+    Ok(abi)
+}
+
+/// Parses one item per line, blank lines and `//` comments are skipped
+pub fn parse_all(s: &str) -> ethabi::Result<Vec<Abi>> {
+    s.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//"))
+        .map(parse)
+        .collect()
+}
+
+struct Param {
+    r#type: String,
+    components: Option<Vec<Param>>,
+    indexed: bool,
+    name: String,
+}
+
+impl Param {
+    fn into_variable(self) -> Variable {
+        Variable {
+            name: self.name,
+            r#type: self.r#type,
+            components: self.components.map(|c| c.into_iter().map(Param::into_variable).collect()),
+            internalType: None,
+        }
+    }
+
+    fn into_event_variable(self) -> EventVariable {
+        EventVariable {
+            name: self.name,
+            r#type: self.r#type,
+            components: self.components.map(|c| c.into_iter().map(Param::into_event_variable).collect()),
+            indexed: self.indexed,
+            internalType: None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.chars().peekable(),
+        }
+    }
+
+    fn error(&self, expected: &str) -> ethabi::Error {
+        ethabi::Error::Other(format!("Expected {} in `{}`", expected, self.input.trim()))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().cloned()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> ethabi::Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", c)))
+        }
+    }
+
+    fn word(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let mut word = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                word.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if word.is_empty() {
+            None
+        } else {
+            Some(word)
+        }
+    }
+
+    fn end(&mut self) -> ethabi::Result<()> {
+        self.eat(';');
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("end of input")),
+        }
+    }
+
+    fn abi(&mut self) -> ethabi::Result<Abi> {
+        let kind = self.word().ok_or_else(|| self.error("`function`, `event`, `error`, `constructor`, `fallback` or `receive`"))?;
+        match kind.as_str() {
+            "function" => {
+                let name = self.word().ok_or_else(|| self.error("function name"))?;
+                let inputs = self.params()?;
+                let mut state_mutability = "nonpayable".to_owned();
+                let mut outputs = Vec::new();
+                while let Some(modifier) = self.word() {
+                    match modifier.as_str() {
+                        "view" | "pure" | "payable" | "nonpayable" => state_mutability = modifier,
+                        "constant" => state_mutability = "view".to_owned(),
+                        "external" | "public" => {},
+                        "returns" => outputs = self.params()?,
+                        _ => return Err(self.error("function modifier")),
+                    }
+                }
+
+                Ok(Abi::Function(Function {
+                    r#type: "function".to_owned(),
+                    name,
+                    inputs: inputs.into_iter().map(Param::into_variable).collect(),
+                    outputs: outputs.into_iter().map(Param::into_variable).collect(),
+                    stateMutability: state_mutability,
+                }))
+            },
+            "constructor" => {
+                let inputs = self.params()?;
+                let state_mutability = self.state_mutability()?;
+
+                Ok(Abi::Constructor(Constructor {
+                    r#type: "constructor".to_owned(),
+                    inputs: inputs.into_iter().map(Param::into_variable).collect(),
+                    stateMutability: state_mutability,
+                }))
+            },
+            "fallback" | "receive" => {
+                if !self.params()?.is_empty() {
+                    return Err(self.error("no parameters"));
+                }
+                let state_mutability = self.state_mutability()?;
+
+                Ok(Abi::Fallback(Fallback {
+                    r#type: kind,
+                    stateMutability: state_mutability,
+                }))
+            },
+            "event" => {
+                let name = self.word().ok_or_else(|| self.error("event name"))?;
+                let inputs = self.params()?;
+                let anonymous = match self.word() {
+                    Some(ref w) if w == "anonymous" => true,
+                    Some(_) => return Err(self.error("`anonymous`")),
+                    None => false,
+                };
+
+                Ok(Abi::Event(Event {
+                    r#type: "event".to_owned(),
+                    name,
+                    inputs: inputs.into_iter().map(Param::into_event_variable).collect(),
+                    anonymous,
+                }))
+            },
+            "error" => {
+                let name = self.word().ok_or_else(|| self.error("error name"))?;
+                let inputs = self.params()?;
+
+                Ok(Abi::Error(Error {
+                    r#type: "error".to_owned(),
+                    name,
+                    inputs: inputs.into_iter().map(Param::into_variable).collect(),
+                }))
+            },
+            _ => Err(self.error("`function`, `event`, `error`, `constructor`, `fallback` or `receive`")),
+        }
+    }
+
+    fn state_mutability(&mut self) -> ethabi::Result<String> {
+        let mut state_mutability = "nonpayable".to_owned();
+        while let Some(modifier) = self.word() {
+            match modifier.as_str() {
+                "payable" | "nonpayable" => state_mutability = modifier,
+                "external" | "public" => {},
+                _ => return Err(self.error("`payable`")),
+            }
+        }
+        Ok(state_mutability)
+    }
+
+    fn params(&mut self) -> ethabi::Result<Vec<Param>> {
+        self.expect('(')?;
+        let mut params = Vec::new();
+        if self.eat(')') {
+            return Ok(params);
+        }
+
+        loop {
+            params.push(self.param()?);
+            if self.eat(')') {
+                return Ok(params);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn param(&mut self) -> ethabi::Result<Param> {
+        let (r#type, components) = if self.peek() == Some('(') {
+            (String::from("tuple"), Some(self.params()?))
+        } else {
+            match self.word().as_deref() {
+                Some("tuple") => (String::from("tuple"), Some(self.params()?)),
+                Some("uint") => (String::from("uint256"), None),
+                Some("int") => (String::from("int256"), None),
+                Some(word) => (word.to_owned(), None),
+                None => return Err(self.error("parameter type")),
+            }
+        };
+
+        let mut r#type = r#type;
+        while self.eat('[') {
+            r#type.push('[');
+            while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+                r#type.push(c);
+                self.chars.next();
+            }
+            self.expect(']')?;
+            r#type.push(']');
+        }
+
+        let mut indexed = false;
+        let mut name = String::new();
+        while let Some(word) = self.word() {
+            match word.as_str() {
+                "indexed" => indexed = true,
+                "memory" | "calldata" | "storage" => {},
+                _ if name.is_empty() => name = word,
+                _ => return Err(self.error("`,` or `)`")),
+            }
+        }
+
+        Ok(Param {
+            r#type,
+            components,
+            indexed,
+            name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_function() {
+        let abi = parse("function transfer(address to, uint amount) returns (bool)").unwrap();
+
+        match &abi {
+            Abi::Function(func) => {
+                assert_eq!(func.name, "transfer");
+                assert_eq!(func.inputs.len(), 2);
+                assert_eq!(func.inputs[0].name, "to");
+                assert_eq!(func.inputs[0].r#type, "address");
+                assert_eq!(func.inputs[1].name, "amount");
+                assert_eq!(func.inputs[1].r#type, "uint256");
+                assert_eq!(func.outputs.len(), 1);
+                assert_eq!(func.outputs[0].name, "");
+                assert_eq!(func.outputs[0].r#type, "bool");
+                assert_eq!(func.stateMutability, "nonpayable");
+            },
+            _ => unreachable!(),
+        }
+
+        assert_eq!(format(&abi), "function transfer(address to, uint256 amount) returns (bool)");
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let abi = parse("event Transfer(address indexed from, address indexed to, uint256 value)").unwrap();
+
+        match &abi {
+            Abi::Event(event) => {
+                assert_eq!(event.name, "Transfer");
+                assert!(!event.anonymous);
+                assert_eq!(event.inputs.len(), 3);
+                assert!(event.inputs[0].indexed);
+                assert!(event.inputs[1].indexed);
+                assert!(!event.inputs[2].indexed);
+                assert_eq!(event.inputs[2].name, "value");
+            },
+            _ => unreachable!(),
+        }
+
+        assert_eq!(format(&abi), "event Transfer(address indexed from, address indexed to, uint256 value)");
+    }
+
+    #[test]
+    fn test_parse_tuples() {
+        let abi = parse("function f(tuple(uint256 a, (address b, bytes c)[] d)[2] s, string memory t) view returns (uint256[] r)").unwrap();
+
+        match &abi {
+            Abi::Function(func) => {
+                assert_eq!(func.inputs[0].r#type, "tuple[2]");
+                let components = func.inputs[0].components.as_ref().unwrap();
+                assert_eq!(components[1].name, "d");
+                assert_eq!(components[1].r#type, "tuple[]");
+                assert_eq!(components[1].components.as_ref().unwrap()[1].r#type, "bytes");
+                assert_eq!(func.inputs[1].name, "t");
+                assert_eq!(func.outputs[0].r#type, "uint256[]");
+                assert_eq!(func.stateMutability, "view");
+                assert_eq!(func.signature().unwrap(), "f((uint256,(address,bytes)[])[2],string)");
+            },
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            format(&abi),
+            "function f(tuple(uint256 a, tuple(address b, bytes c)[] d)[2] s, string t) view returns (uint256[] r)"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lines = vec![
+            "constructor(bytes32[] proposalNames) payable",
+            "function owner() view returns (address)",
+            "function deposit() payable",
+            "event Deposit(address indexed from, uint256 amount) anonymous",
+            "error InsufficientBalance(uint256 available, uint256 required)",
+            "fallback() external payable",
+            "receive() external payable",
+        ];
+
+        let abis = parse_all(&lines.join("\n")).unwrap();
+        assert_eq!(abis.len(), lines.len());
+        assert_eq!(format_all(&abis), lines);
+    }
+
+    #[test]
+    fn test_parse_json_equivalent() {
+        let json = Abi::from_json_array(r#"[
+            {
+              "inputs": [
+                { "name": "new_address", "type": "address" }
+              ],
+              "name": "upgrade",
+              "outputs": [],
+              "stateMutability": "nonpayable",
+              "type": "function"
+            }
+        ]"#).unwrap();
+
+        assert_eq!(parse_all("// Migrations\nfunction upgrade(address new_address)\n").unwrap(), json);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("function").is_err());
+        assert!(parse("function f(uint256").is_err());
+        assert!(parse("function f() returns").is_err());
+        assert!(parse("function f() sometimes").is_err());
+        assert!(parse("struct S(uint256 a)").is_err());
+        assert!(parse("event E(uint256 a b)").is_err());
+    }
+}
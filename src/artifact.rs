@@ -0,0 +1,171 @@
+//! Layouts of the JSON artifacts written for the contracts. Besides the
+//! native one, artifacts can be shared with the tools of other frameworks.
+
+use crate::contract::Contract;
+use chrono::Utc;
+use crate::linker::{self, LinkReference};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub mod foundry;
+pub mod hardhat;
+pub mod truffle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    #[default]
+    Native,
+    Truffle,
+    Hardhat,
+    Foundry,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(Format::Native),
+            "truffle" => Ok(Format::Truffle),
+            "hardhat" => Ok(Format::Hardhat),
+            "foundry" => Ok(Format::Foundry),
+            _ => Err(format!("Unsupported artifact format {}", s)),
+        }
+    }
+}
+
+impl Format {
+    /// Whether the artifacts record the deployments, Hardhat and Foundry ones
+    /// keep them in a file of their own, see `networks_path`
+    pub fn has_networks(self) -> bool {
+        matches!(self, Format::Native | Format::Truffle)
+    }
+
+    /// Guesses the format of a parsed artifact from its fields
+    pub fn detect(json: &Value) -> Self {
+        if json.get("_format").is_some() {
+            Format::Hardhat
+        } else if json["bytecode"].is_object() {
+            Format::Foundry
+        } else if json.get("contractName").is_some() {
+            Format::Truffle
+        } else {
+            Format::Native
+        }
+    }
+}
+
+pub fn to_json(contract: &Contract, format: Format, pretty_print: bool) -> serde_json::Result<String> {
+    match format {
+        Format::Native => serialize(contract, pretty_print),
+        Format::Truffle => {
+            // The source is embedded when the file is still around
+            let source = contract.source_path.as_ref()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            let artifact = truffle::Artifact::from(contract)
+                .with_source(source)
+                .with_updated_at(Utc::now());
+            serialize(&artifact, pretty_print)
+        },
+        Format::Hardhat => serialize(&hardhat::Artifact::from(contract), pretty_print),
+        Format::Foundry => serialize(&foundry::Artifact::from(contract), pretty_print),
+    }
+}
+
+/// Reads an artifact of any format
+pub fn from_json(json: &str) -> serde_json::Result<Contract> {
+    let (_, contract) = from_json_unnamed(json)?;
+    if contract.name.is_empty() {
+        return Err(serde::de::Error::custom("The artifact doesn't tell the name of its contract"));
+    }
+    Ok(contract)
+}
+
+/// Reads an artifact of any format along with the format, the contract is
+/// left unnamed when the artifact doesn't tell its name, as Foundry ones
+/// without metadata
+pub(crate) fn from_json_unnamed(json: &str) -> serde_json::Result<(Format, Contract)> {
+    let value: Value = serde_json::from_str(json)?;
+    let format = Format::detect(&value);
+    let contract = match format {
+        Format::Native => serde_json::from_value(value),
+        Format::Truffle => serde_json::from_value::<truffle::Artifact>(value).map(Contract::from),
+        Format::Hardhat => serde_json::from_value::<hardhat::Artifact>(value).map(Contract::from),
+        Format::Foundry => serde_json::from_value::<foundry::Artifact>(value).map(Contract::from),
+    }?;
+    Ok((format, contract))
+}
+
+/// File keeping the deployments of an artifact whose format can't, e.g.
+/// `Token.networks.json` for `Token.json`
+pub fn networks_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.networks.json", stem))
+}
+
+fn serialize(value: &impl Serialize, pretty_print: bool) -> serde_json::Result<String> {
+    if pretty_print {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+fn prefix_hex(bin: &str) -> String {
+    format!("0x{}", bin)
+}
+
+fn strip_hex(bin: &str) -> String {
+    bin.trim_start_matches("0x").to_owned()
+}
+
+/// Offsets of the libraries keyed by source and name, as written by Hardhat
+/// and Foundry
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, Vec<LinkOffset>>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkOffset {
+    pub start: usize,
+    pub length: usize,
+}
+
+fn to_link_references(references: &[LinkReference]) -> LinkReferences {
+    let mut output = LinkReferences::new();
+    for reference in references.iter() {
+        let (source, name) = match reference.name.rfind(':') {
+            Some(i) => (&reference.name[..i], &reference.name[i + 1..]),
+            None => ("", &reference.name[..]),
+        };
+        output.entry(source.to_owned())
+            .or_default()
+            .insert(name.to_owned(), reference.offsets.iter().map(|&start| LinkOffset { start, length: 20 }).collect());
+    }
+    output
+}
+
+/// Placeholders of `bin` named after the references
+fn from_link_references(bin: &str, references: &LinkReferences) -> Vec<LinkReference> {
+    let mut names = HashMap::new();
+    for (source, libraries) in references.iter() {
+        for (name, offsets) in libraries.iter() {
+            let placeholder = offsets.first().and_then(|o| bin.get(o.start * 2..(o.start + o.length) * 2));
+            if let Some(placeholder) = placeholder {
+                names.insert(placeholder.to_owned(), format!("{}:{}", source, name));
+            }
+        }
+    }
+    linker::find_references(bin, &names)
+}
+
+/// References of the runtime code, named after the ones of the creation code
+fn runtime_link_references(contract: &Contract) -> Vec<LinkReference> {
+    let names = contract.link_references.iter()
+        .map(|r| (r.placeholder.clone(), r.name.clone()))
+        .collect();
+    linker::find_references(&contract.bin_runtime, &names)
+}
@@ -0,0 +1,145 @@
+//! Artifacts as written by `forge build`. They don't have the name of the
+//! contract besides the compilation target of the metadata, without metadata
+//! it's the name of the file.
+
+use super::LinkReferences;
+use crate::abi::Abi;
+use crate::contract::Contract;
+use rustc_hex::ToHex;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub abi: Vec<Abi>,
+    pub bytecode: Bytecode,
+    #[serde(default)]
+    pub deployed_bytecode: Bytecode,
+    /// Selectors in hex keyed by signature
+    #[serde(default)]
+    pub method_identifiers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_metadata: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub metadata: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bytecode {
+    /// `0x` prefixed code
+    pub object: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<String>,
+    #[serde(default)]
+    pub link_references: LinkReferences,
+}
+
+impl Artifact {
+    /// The contract named in `metadata.settings.compilationTarget`
+    pub fn contract_name(&self) -> Option<(&str, &str)> {
+        self.metadata["settings"]["compilationTarget"]
+            .as_object()?
+            .iter()
+            .find_map(|(source, name)| Some((source.as_str(), name.as_str()?)))
+    }
+}
+
+impl From<&Contract> for Artifact {
+    fn from(contract: &Contract) -> Self {
+        Self {
+            abi: contract.abi.clone(),
+            bytecode: Bytecode {
+                object: super::prefix_hex(&contract.bin),
+                source_map: contract.source_map.clone(),
+                link_references: super::to_link_references(&contract.link_references),
+            },
+            deployed_bytecode: Bytecode {
+                object: super::prefix_hex(&contract.bin_runtime),
+                source_map: contract.source_map_runtime.clone(),
+                link_references: super::to_link_references(&super::runtime_link_references(contract)),
+            },
+            method_identifiers: contract.selectors()
+                .into_iter()
+                .map(|(signature, selector)| (signature, selector.to_hex()))
+                .collect(),
+            raw_metadata: contract.raw_metadata.clone(),
+            metadata: contract.raw_metadata.as_ref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Artifact> for Contract {
+    fn from(artifact: Artifact) -> Self {
+        let (source, name) = artifact.contract_name()
+            .map(|(source, name)| (source.to_owned(), name.to_owned()))
+            .unwrap_or_default();
+
+        let mut contract = Contract::new(name, artifact.abi, super::strip_hex(&artifact.bytecode.object));
+        contract.bin_runtime = super::strip_hex(&artifact.deployed_bytecode.object);
+        contract.source_map = artifact.bytecode.source_map.filter(|m| !m.is_empty());
+        contract.source_map_runtime = artifact.deployed_bytecode.source_map.filter(|m| !m.is_empty());
+        contract.link_references = super::from_link_references(&contract.bin, &artifact.bytecode.link_references);
+        contract.raw_metadata = artifact.raw_metadata;
+        if !source.is_empty() {
+            contract.source_path = Some(source);
+        }
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+    use crate::artifact::Format;
+
+    #[test]
+    fn test_read_foundry() {
+        let json = r#"{
+            "abi": [
+                { "inputs": [], "name": "increment", "outputs": [], "stateMutability": "nonpayable", "type": "function" }
+            ],
+            "bytecode": { "object": "0x6080604052", "sourceMap": "65:184:0:-:0;;;", "linkReferences": {} },
+            "deployedBytecode": { "object": "0x60806040", "sourceMap": "65:184:0:-:0;;;", "linkReferences": {} },
+            "methodIdentifiers": { "increment()": "d09de08a" },
+            "metadata": { "settings": { "compilationTarget": { "src/Counter.sol": "Counter" } } },
+            "id": 20
+        }"#;
+        let contract = Contract::from_json(json).unwrap();
+
+        assert_eq!(contract.name, "Counter");
+        assert_eq!(contract.source_path.as_deref(), Some("src/Counter.sol"));
+        assert_eq!(contract.bin, "6080604052");
+        assert_eq!(contract.bin_runtime, "60806040");
+        assert_eq!(contract.source_map.as_deref(), Some("65:184:0:-:0;;;"));
+        assert!(contract.get_function_by_selector([0xd0, 0x9d, 0xe0, 0x8a]).is_some());
+    }
+
+    #[test]
+    fn test_write_foundry() {
+        let abi = human::parse_all("function setCompleted(uint256 completed)").unwrap();
+        let contract = Contract::new("Migrations".to_owned(), abi, "6080604052".to_owned());
+        let json: serde_json::Value = serde_json::from_str(&contract.to_json(Format::Foundry, false)).unwrap();
+
+        assert_eq!(json["bytecode"]["object"], "0x6080604052");
+        assert_eq!(json["deployedBytecode"]["object"], "0x");
+        assert_eq!(json["methodIdentifiers"]["setCompleted(uint256)"], "fdacd576");
+        assert!(json.get("metadata").is_none());
+
+        // Named after the file, the artifact alone doesn't tell the name
+        assert!(Contract::from_json(&json.to_string()).is_err());
+        let dir = tempfile::tempdir().unwrap();
+        contract.write_to_dir_as(dir.path(), Format::Foundry, false).unwrap();
+        assert_eq!(Contract::read_from_dir(dir.path(), "Migrations").unwrap(), contract);
+
+        let mut contract = contract;
+        contract.raw_metadata = Some(r#"{"settings":{"compilationTarget":{"contracts/Migrations.sol":"Migrations"}}}"#.to_owned());
+        contract.source_path = Some("contracts/Migrations.sol".to_owned());
+        assert_eq!(Contract::from_json(&contract.to_json(Format::Foundry, false)).unwrap(), contract);
+    }
+}
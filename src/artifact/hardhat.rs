@@ -0,0 +1,124 @@
+//! Artifacts as written by `hardhat compile`
+
+use super::LinkReferences;
+use crate::abi::Abi;
+use crate::contract::Contract;
+use serde::{Serialize, Deserialize};
+
+pub const FORMAT: &str = "hh-sol-artifact-1";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    #[serde(rename = "_format")]
+    pub format: String,
+    pub contract_name: String,
+    #[serde(default)]
+    pub source_name: String,
+    pub abi: Vec<Abi>,
+    /// `0x` prefixed creation code
+    pub bytecode: String,
+    #[serde(default)]
+    pub deployed_bytecode: String,
+    #[serde(default)]
+    pub link_references: LinkReferences,
+    #[serde(default)]
+    pub deployed_link_references: LinkReferences,
+}
+
+impl From<&Contract> for Artifact {
+    fn from(contract: &Contract) -> Self {
+        Self {
+            format: FORMAT.to_owned(),
+            contract_name: contract.name.clone(),
+            source_name: contract.source_path.clone().unwrap_or_default(),
+            abi: contract.abi.clone(),
+            bytecode: super::prefix_hex(&contract.bin),
+            deployed_bytecode: super::prefix_hex(&contract.bin_runtime),
+            link_references: super::to_link_references(&contract.link_references),
+            deployed_link_references: super::to_link_references(&super::runtime_link_references(contract)),
+        }
+    }
+}
+
+impl From<Artifact> for Contract {
+    fn from(artifact: Artifact) -> Self {
+        let mut contract = Contract::new(artifact.contract_name, artifact.abi, super::strip_hex(&artifact.bytecode));
+        contract.bin_runtime = super::strip_hex(&artifact.deployed_bytecode);
+        contract.link_references = super::from_link_references(&contract.bin, &artifact.link_references);
+        if !artifact.source_name.is_empty() {
+            contract.source_path = Some(artifact.source_name);
+        }
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::Format;
+
+    #[test]
+    fn test_read_hardhat() {
+        let json = r#"{
+            "_format": "hh-sol-artifact-1",
+            "contractName": "Greeter",
+            "sourceName": "contracts/Greeter.sol",
+            "abi": [
+                { "inputs": [], "name": "greet", "outputs": [{ "internalType": "string", "name": "", "type": "string" }], "stateMutability": "view", "type": "function" }
+            ],
+            "bytecode": "0x6080604052",
+            "deployedBytecode": "0x60806040",
+            "linkReferences": {},
+            "deployedLinkReferences": {}
+        }"#;
+        let contract = Contract::from_json(json).unwrap();
+
+        assert_eq!(contract.name, "Greeter");
+        assert_eq!(contract.source_path.as_deref(), Some("contracts/Greeter.sol"));
+        assert_eq!(contract.bin, "6080604052");
+        assert_eq!(contract.bin_runtime, "60806040");
+        assert!(contract.get_function("greet()").is_some());
+    }
+
+    #[test]
+    fn test_link_references() {
+        let math = crate::linker::placeholder("contracts/Math.sol:Math");
+        let json = format!(r#"{{
+            "_format": "hh-sol-artifact-1",
+            "contractName": "Vault",
+            "sourceName": "contracts/Vault.sol",
+            "abi": [],
+            "bytecode": "0x6080{math}00",
+            "deployedBytecode": "0x60{math}",
+            "linkReferences": {{ "contracts/Math.sol": {{ "Math": [{{ "length": 20, "start": 2 }}] }} }},
+            "deployedLinkReferences": {{ "contracts/Math.sol": {{ "Math": [{{ "length": 20, "start": 1 }}] }} }}
+        }}"#, math = math);
+        let contract = Contract::from_json(&json).unwrap();
+
+        assert_eq!(contract.link_references.len(), 1);
+        assert_eq!(contract.link_references[0].name, "contracts/Math.sol:Math");
+        assert_eq!(contract.link_references[0].offsets, vec![2]);
+
+        let written: serde_json::Value = serde_json::from_str(&contract.to_json(Format::Hardhat, false)).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(written["linkReferences"], expected["linkReferences"]);
+        assert_eq!(written["deployedLinkReferences"], expected["deployedLinkReferences"]);
+    }
+
+    #[test]
+    fn test_write_hardhat() {
+        let mut contract = Contract::new("Greeter".to_owned(), Vec::new(), "6080604052".to_owned());
+        contract.source_path = Some("contracts/Greeter.sol".to_owned());
+        let json: serde_json::Value = serde_json::from_str(&contract.to_json(Format::Hardhat, false)).unwrap();
+
+        assert_eq!(json["_format"], "hh-sol-artifact-1");
+        assert_eq!(json["contractName"], "Greeter");
+        assert_eq!(json["sourceName"], "contracts/Greeter.sol");
+        assert_eq!(json["bytecode"], "0x6080604052");
+        assert_eq!(json["deployedBytecode"], "0x");
+        assert_eq!(json["linkReferences"], serde_json::json!({}));
+
+        assert_eq!(Contract::from_json(&json.to_string()).unwrap(), contract);
+    }
+}
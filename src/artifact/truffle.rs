@@ -0,0 +1,293 @@
+//! Artifacts as written by `truffle compile`, readable by `truffle migrate`
+//! and `@truffle/contract`
+
+use crate::abi::Abi;
+use crate::contract::{Address, Contract, Network as ContractNetwork};
+use crate::linker;
+use chrono::{DateTime, SecondsFormat, Utc};
+use ethereum_types::H256;
+use rustc_hex::{FromHex, ToHex};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+const SCHEMA_VERSION: &str = "3.0.23";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub contract_name: String,
+    pub abi: Vec<Abi>,
+    /// Metadata JSON of solc
+    #[serde(default)]
+    pub metadata: String,
+    /// `0x` prefixed creation code
+    pub bytecode: String,
+    #[serde(default)]
+    pub deployed_bytecode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_source_map: Option<String>,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub source_path: String,
+    #[serde(default)]
+    pub compiler: Compiler,
+    #[serde(default)]
+    pub networks: HashMap<String, Network>,
+    #[serde(default)]
+    pub schema_version: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Compiler {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Network {
+    #[serde(default)]
+    pub events: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub links: HashMap<String, Address>,
+    pub address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<H256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployer: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// `0x` prefixed ABI encoding of the arguments of the constructor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor_args: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytecode_hash: Option<H256>,
+}
+
+impl Artifact {
+    pub fn with_source(mut self, source: String) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_updated_at(mut self, updated_at: DateTime<Utc>) -> Self {
+        self.updated_at = updated_at.to_rfc3339_opts(SecondsFormat::Millis, true);
+        self
+    }
+}
+
+/// Leaves out the source and the time of the update, see `with_source` and
+/// `with_updated_at`
+impl From<&Contract> for Artifact {
+    fn from(contract: &Contract) -> Self {
+        Self {
+            contract_name: contract.name.clone(),
+            abi: contract.abi.clone(),
+            metadata: contract.raw_metadata.clone().unwrap_or_default(),
+            bytecode: super::prefix_hex(&contract.bin),
+            deployed_bytecode: super::prefix_hex(&contract.bin_runtime),
+            source_map: contract.source_map.clone(),
+            deployed_source_map: contract.source_map_runtime.clone(),
+            source: String::new(),
+            source_path: contract.source_path.clone().unwrap_or_default(),
+            compiler: Compiler {
+                name: "solc".to_owned(),
+                version: contract.compiler_version.clone().unwrap_or_default(),
+            },
+            networks: contract.networks.iter()
+                .map(|(id, n)| (id.clone(), Network {
+                    events: HashMap::new(),
+                    links: n.libraries().iter().map(|(name, address)| (name.clone(), *address)).collect(),
+                    address: n.address(),
+                    transaction_hash: n.transaction_hash(),
+                    block_number: n.block_number(),
+                    deployer: n.deployer(),
+                    timestamp: n.timestamp(),
+                    constructor_args: n.constructor_args().map(|args| format!("0x{}", args.to_hex::<String>())),
+                    bytecode_hash: n.bytecode_hash(),
+                }))
+                .collect(),
+            schema_version: SCHEMA_VERSION.to_owned(),
+            updated_at: String::new(),
+        }
+    }
+}
+
+impl From<Artifact> for Contract {
+    fn from(artifact: Artifact) -> Self {
+        let mut contract = Contract::new(artifact.contract_name, artifact.abi, super::strip_hex(&artifact.bytecode));
+        contract.bin_runtime = super::strip_hex(&artifact.deployed_bytecode);
+        contract.source_map = artifact.source_map.filter(|m| !m.is_empty());
+        contract.source_map_runtime = artifact.deployed_source_map.filter(|m| !m.is_empty());
+        contract.link_references = linker::find_references(&contract.bin, &HashMap::new());
+        contract.networks = artifact.networks.into_iter()
+            .map(|(id, n)| {
+                let network = n.links.iter()
+                    .fold(ContractNetwork::new(n.address), |network, (name, address)| network.with_library(name, *address));
+                let network = match n.transaction_hash {
+                    Some(hash) => network.with_transaction_hash(hash),
+                    None => network,
+                };
+                let network = match n.block_number {
+                    Some(block_number) => network.with_block_number(block_number),
+                    None => network,
+                };
+                let network = match n.deployer {
+                    Some(deployer) => network.with_deployer(deployer),
+                    None => network,
+                };
+                let network = match n.timestamp {
+                    Some(timestamp) => network.with_timestamp(timestamp),
+                    None => network,
+                };
+                let network = match n.constructor_args.and_then(|args| args.trim_start_matches("0x").from_hex::<Vec<u8>>().ok()) {
+                    Some(args) => network.with_constructor_args(&args),
+                    None => network,
+                };
+                let network = match n.bytecode_hash {
+                    Some(hash) => network.with_bytecode_hash(hash),
+                    None => network,
+                };
+                (id, network)
+            })
+            .collect();
+        if !artifact.metadata.is_empty() {
+            contract.raw_metadata = Some(artifact.metadata);
+        }
+        if !artifact.source_path.is_empty() {
+            contract.source_path = Some(artifact.source_path);
+        }
+        if !artifact.compiler.version.is_empty() {
+            contract.compiler_version = Some(artifact.compiler.version);
+        }
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+    use crate::artifact::{self, Format};
+    use std::fs;
+
+    fn contract() -> Contract {
+        let abi = human::parse_all("
+            function setCompleted(uint256 completed)
+            event Upgraded(address indexed implementation)
+        ").unwrap();
+        let mut contract = Contract::new("Migrations".to_owned(), abi, "6080604052".to_owned());
+        contract.bin_runtime = "60806040".to_owned();
+        contract.source_map = Some("25:480:0:-:0;;;".to_owned());
+        contract.source_map_runtime = Some("25:480:0:-:0;;;;8:9:-1".to_owned());
+        contract.compiler_version = Some("0.6.2+commit.bacdbe57".to_owned());
+        contract.networks.insert(
+            "5777".to_owned(),
+            ContractNetwork::new("e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap())
+                .with_transaction_hash("f2b9e5c7d0a7e3a6d2e2b6f0a5e4a0f7c1b2d3e4f5a6b7c8d9e0f1a2b3c4d5e6".parse().unwrap())
+                .with_block_number(12)
+                .with_deployer(Address::from_low_u64_be(0x1001))
+                .with_timestamp(1_600_000_000)
+                .with_constructor_args(&[0, 42])
+                .with_library("Math", Address::from_low_u64_be(1))
+                .with_bytecode_hash(H256::from_low_u64_be(0xcafe)),
+        );
+        contract
+    }
+
+    #[test]
+    fn test_write_truffle() {
+        let json: serde_json::Value = serde_json::from_str(&contract().to_json(Format::Truffle, false)).unwrap();
+
+        assert_eq!(json["contractName"], "Migrations");
+        assert_eq!(json["bytecode"], "0x6080604052");
+        assert_eq!(json["deployedBytecode"], "0x60806040");
+        assert_eq!(json["sourceMap"], "25:480:0:-:0;;;");
+        assert_eq!(json["deployedSourceMap"], "25:480:0:-:0;;;;8:9:-1");
+        assert_eq!(json["compiler"]["name"], "solc");
+        assert_eq!(json["compiler"]["version"], "0.6.2+commit.bacdbe57");
+        assert_eq!(json["networks"]["5777"]["address"], "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab");
+        assert_eq!(
+            json["networks"]["5777"]["transactionHash"],
+            "0xf2b9e5c7d0a7e3a6d2e2b6f0a5e4a0f7c1b2d3e4f5a6b7c8d9e0f1a2b3c4d5e6"
+        );
+        assert_eq!(json["networks"]["5777"]["blockNumber"], 12);
+        assert_eq!(json["networks"]["5777"]["deployer"], "0x0000000000000000000000000000000000001001");
+        assert_eq!(json["networks"]["5777"]["timestamp"], 1_600_000_000);
+        assert_eq!(json["networks"]["5777"]["constructorArgs"], "0x002a");
+        assert_eq!(json["networks"]["5777"]["links"]["Math"], "0x0000000000000000000000000000000000000001");
+        assert_eq!(json["abi"][0]["name"], "setCompleted");
+        assert!(json["updatedAt"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_write_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source_path = dir.path().join("Migrations.sol");
+        fs::write(&source_path, "contract Migrations {}").unwrap();
+        let mut contract = contract();
+        contract.source_path = Some(source_path.to_string_lossy().into_owned());
+
+        let artifact = Artifact::from(&contract);
+        assert_eq!(artifact.source, "");
+        assert_eq!(artifact.updated_at, "");
+
+        let json: serde_json::Value = serde_json::from_str(&contract.to_json(Format::Truffle, false)).unwrap();
+        assert_eq!(json["source"], "contract Migrations {}");
+        assert_eq!(json["sourcePath"], source_path.to_string_lossy().as_ref());
+    }
+
+    #[test]
+    fn test_read_truffle() {
+        let json = r#"{
+            "contractName": "Migrations",
+            "abi": [],
+            "bytecode": "0x6080604052",
+            "deployedBytecode": "0x60806040",
+            "sourceMap": "25:480:0:-;;;",
+            "sourcePath": "/home/user/project/contracts/Migrations.sol",
+            "compiler": { "name": "solc", "version": "0.5.16+commit.9c3226ce.Emscripten.clang" },
+            "networks": {
+                "5777": {
+                    "events": {},
+                    "links": { "Math": "0x0000000000000000000000000000000000000001" },
+                    "address": "0xE78A0F7E598Cc8b0Bb87894B0F60dD2a88d6a8Ab",
+                    "transactionHash": "0xf2b9e5c7d0a7e3a6d2e2b6f0a5e4a0f7c1b2d3e4f5a6b7c8d9e0f1a2b3c4d5e6"
+                }
+            },
+            "schemaVersion": "3.0.23",
+            "updatedAt": "2020-02-11T18:47:03.263Z"
+        }"#;
+        let contract = Contract::from_json(json).unwrap();
+
+        assert_eq!(contract.name, "Migrations");
+        assert_eq!(contract.bin, "6080604052");
+        assert_eq!(contract.bin_runtime, "60806040");
+        assert_eq!(contract.source_map.as_deref(), Some("25:480:0:-;;;"));
+        assert!(contract.source_map_runtime.is_none());
+        assert_eq!(contract.source_path.as_deref(), Some("/home/user/project/contracts/Migrations.sol"));
+        assert_eq!(contract.get_address("5777"), Some("e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap()));
+        assert_eq!(
+            contract.networks["5777"].transaction_hash(),
+            Some("f2b9e5c7d0a7e3a6d2e2b6f0a5e4a0f7c1b2d3e4f5a6b7c8d9e0f1a2b3c4d5e6".parse().unwrap())
+        );
+        assert_eq!(contract.networks["5777"].libraries()["Math"], Address::from_low_u64_be(1));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let contract = contract();
+        let json = artifact::to_json(&contract, Format::Truffle, true).unwrap();
+        assert_eq!(artifact::from_json(&json).unwrap(), contract);
+
+        let json = artifact::to_json(&contract, Format::Native, true).unwrap();
+        assert_eq!(artifact::from_json(&json).unwrap(), contract);
+    }
+}
@@ -0,0 +1,164 @@
+//! Typed bindings generated from compiled contracts.
+//!
+//! Can be called from a `build.rs` to regenerate the bindings whenever the
+//! contracts change:
+//! ```no_run
+//! use tortilla::bindgen::{self, Lang};
+//! use tortilla::compiler::compile_path;
+//!
+//! let contracts = compile_path("contracts").unwrap();
+//! bindgen::write_to_dir(&contracts, Lang::Rust, "src/bindings").unwrap();
+//! ```
+
+use crate::abi::ParamType;
+use crate::contract::Contract;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub mod rust;
+pub mod typescript;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    Rust,
+    TypeScript,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rust" => Ok(Lang::Rust),
+            "typescript" | "ts" => Ok(Lang::TypeScript),
+            _ => Err(format!("Unsupported language {}", s)),
+        }
+    }
+}
+
+/// Writes one file per contract, plus the `mod.rs` declaring them for Rust or
+/// the `index.ts` exporting them for TypeScript
+pub fn write_to_dir(contracts: &[Contract], lang: Lang, dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut files = Vec::new();
+    for contract in contracts.iter() {
+        let (name, content) = match lang {
+            Lang::Rust => (format!("{}.rs", rust::module_name(&contract.name)), rust::generate(contract)),
+            Lang::TypeScript => (format!("{}.ts", contract.name), typescript::generate(contract)),
+        };
+        let content = content.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", contract.name, e)))?;
+
+        files.push(write_file(dir.join(name), &content)?);
+    }
+
+    match lang {
+        Lang::Rust => files.push(write_file(dir.join("mod.rs"), &rust::generate_mod(contracts))?),
+        Lang::TypeScript => files.push(write_file(dir.join("index.ts"), &typescript::generate_index(contracts))?),
+    }
+
+    Ok(files)
+}
+
+fn write_file(path: PathBuf, content: &str) -> io::Result<PathBuf> {
+    let mut file = File::create(&path)?;
+    write!(file, "{}", content)?;
+    Ok(path)
+}
+
+/// `setCompleted` -> `set_completed`, `ERC20Token` -> `erc20_token`
+pub(crate) fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let after_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric());
+            let before_lower = i > 0 && chars[i - 1].is_uppercase() && matches!(chars.get(i + 1), Some(n) if n.is_lowercase());
+            if (after_lower || before_lower) && !output.ends_with('_') {
+                output.push('_');
+            }
+            output.extend(c.to_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// `auction_ended` -> `AuctionEnded`
+pub(crate) fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars.next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Whether an indexed event parameter of this type is logged as the hash of
+/// its value, as strings, bytes, arrays and structs are. The bindings keep the
+/// hash since the value can't be recovered
+pub(crate) fn is_hashed(param_type: &ParamType) -> bool {
+    matches!(
+        param_type,
+        ParamType::Bytes | ParamType::String | ParamType::Array(_) | ParamType::FixedArray(_, _) | ParamType::Tuple(_)
+    )
+}
+
+/// Suffixes repeated names with their position among the items sharing it
+pub(crate) fn unique_names(names: impl Iterator<Item = String>, separator: &str) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    names
+        .map(|name| {
+            let count = seen.entry(name.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                name
+            } else {
+                format!("{}{}{}", name, separator, *count - 1)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conversions() {
+        assert_eq!(snake_case("setCompleted"), "set_completed");
+        assert_eq!(snake_case("last_completed_migration"), "last_completed_migration");
+        assert_eq!(snake_case("ERC20Token"), "erc20_token");
+        assert_eq!(snake_case("HelloWorld"), "hello_world");
+        assert_eq!(snake_case("safeTransferFrom"), "safe_transfer_from");
+        assert_eq!(camel_case("auction_ended"), "AuctionEnded");
+        assert_eq!(camel_case("Transfer"), "Transfer");
+    }
+
+    #[test]
+    fn test_write_rust_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let contracts = ["Box", "ERC20Token", "Self"].iter()
+            .map(|name| Contract::new(name.to_string(), Vec::new(), "6080".to_owned()))
+            .collect::<Vec<Contract>>();
+        write_to_dir(&contracts, Lang::Rust, dir.path()).unwrap();
+
+        let modules = fs::read_to_string(dir.path().join("mod.rs")).unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub mod "))
+            .map(|module| module.trim_end_matches(';').to_owned())
+            .collect::<Vec<String>>();
+        assert_eq!(modules, vec!["box_", "erc20_token", "self_"]);
+        for module in modules.iter() {
+            assert!(dir.path().join(format!("{}.rs", module)).is_file(), "{}.rs is missing", module);
+        }
+    }
+}
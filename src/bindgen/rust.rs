@@ -0,0 +1,453 @@
+//! Rust bindings, one module per contract. The generated code only depends on
+//! the `ethabi` crate:
+//!
+//! * `functions::<name>::encode_input(..)` and `decode_output(..)` per function,
+//!   overloads are suffixed with their position, e.g. `safe_transfer_from_1`
+//!   for functions and `Transfer1` for events
+//! * `events::<Name>` structs with a `decode(topics, data)` function
+//! * `BYTECODE` and `deploy_data(..)` taking the constructor arguments
+
+use super::{camel_case, is_hashed, snake_case, unique_names};
+use crate::abi::{Abi, Constructor, Event, Function, ParamType, Variable};
+use crate::contract::Contract;
+use std::fmt::Write;
+
+macro_rules! emit {
+    ($out:expr) => {
+        writeln!($out).expect("Writing to a String can't fail")
+    };
+    ($out:expr, $($arg:tt)*) => {
+        writeln!($out, $($arg)*).expect("Writing to a String can't fail")
+    };
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do",
+    "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+pub fn generate(contract: &Contract) -> ethabi::Result<String> {
+    let mut out = String::new();
+    emit!(out, "//! Bindings of the `{}` contract, generated by tortilla. Do not edit.", contract.name);
+    emit!(out, "#![allow(dead_code, unused_mut, unused_variables, clippy::all)]");
+    emit!(out);
+    emit!(out, "pub const NAME: &str = \"{}\";", contract.name);
+    emit!(out, "pub const ABI: &str = r##\"{}\"##;", serde_json::to_string(&contract.abi)?);
+    emit!(out, "pub const BYTECODE: &str = \"{}\";", contract.bin);
+    emit!(out);
+
+    let constructor = contract.abi.iter().find_map(|a| match a {
+        Abi::Constructor(c) => Some(c),
+        _ => None,
+    });
+    generate_deploy(&mut out, constructor)?;
+
+    emit!(out);
+    emit!(out, "pub mod functions {{");
+    let functions = contract.abi.iter()
+        .filter_map(|a| match a {
+            Abi::Function(f) => Some(f),
+            _ => None,
+        })
+        .collect::<Vec<&Function>>();
+    let names = unique_names(functions.iter().map(|f| ident(&f.name)), "_");
+    for (function, name) in functions.iter().zip(names) {
+        emit!(out);
+        generate_function(&mut out, function, &name)?;
+    }
+    emit!(out, "}}");
+
+    emit!(out);
+    emit!(out, "pub mod events {{");
+    let events = contract.abi.iter()
+        .filter_map(|a| match a {
+            Abi::Event(e) => Some(e),
+            _ => None,
+        })
+        .collect::<Vec<&Event>>();
+    let names = unique_names(events.iter().map(|e| camel_case(&e.name)), "");
+    for (event, name) in events.iter().zip(names) {
+        emit!(out);
+        generate_event(&mut out, event, &name)?;
+    }
+    emit!(out, "}}");
+
+    Ok(out)
+}
+
+/// `mod.rs` declaring the modules of the contracts
+pub fn generate_mod(contracts: &[Contract]) -> String {
+    let mut out = String::new();
+    emit!(out, "//! Generated by tortilla. Do not edit.");
+    emit!(out);
+    for contract in contracts.iter() {
+        emit!(out, "pub mod {};", module_name(&contract.name));
+    }
+    out
+}
+
+fn generate_deploy(out: &mut String, constructor: Option<&Constructor>) -> ethabi::Result<()> {
+    let inputs = constructor.map(|c| &c.inputs[..]).unwrap_or(&[]);
+    let args = Args::new(inputs)?;
+
+    emit!(out, "pub fn bytecode() -> Vec<u8> {{");
+    emit!(out, "    (0..BYTECODE.len())");
+    emit!(out, "        .step_by(2)");
+    emit!(out, "        .map(|i| u8::from_str_radix(&BYTECODE[i..i + 2], 16).expect(\"Unlinked or invalid bytecode\"))");
+    emit!(out, "        .collect()");
+    emit!(out, "}}");
+    emit!(out);
+    emit!(out, "/// Creation data: the bytecode followed by the encoded constructor arguments");
+    emit!(out, "pub fn deploy_data({}) -> Vec<u8> {{", args.params());
+    emit!(out, "    [self::bytecode(), ethabi::encode(&[{}])].concat()", args.tokens());
+    emit!(out, "}}");
+    Ok(())
+}
+
+fn generate_function(out: &mut String, function: &Function, name: &str) -> ethabi::Result<()> {
+    let args = Args::new(&function.inputs)?;
+    let outputs = function.outputs.iter()
+        .map(Variable::param_type)
+        .collect::<ethabi::Result<Vec<ParamType>>>()?;
+
+    emit!(out, "    /// `{}`", Abi::Function(function.clone()).to_human());
+    emit!(out, "    pub mod {} {{", name);
+    emit!(out, "        pub const SIGNATURE: &str = \"{}\";", function.signature()?);
+    emit!(out, "        pub const SELECTOR: [u8; 4] = {};", bytes_literal(&function.selector()?));
+    emit!(out);
+    emit!(out, "        pub fn encode_input({}) -> Vec<u8> {{", args.params());
+    emit!(out, "            [&SELECTOR[..], &ethabi::encode(&[{}])].concat()", args.tokens());
+    emit!(out, "        }}");
+    emit!(out);
+    if outputs.is_empty() {
+        emit!(out, "        pub fn decode_output(_data: &[u8]) -> ethabi::Result<()> {{");
+        emit!(out, "            Ok(())");
+    } else {
+        emit!(out, "        pub fn decode_output(data: &[u8]) -> ethabi::Result<{}> {{", tuple_type(&outputs));
+        emit!(out, "            let mut tokens = ethabi::decode(&[{}], data)?.into_iter();", param_types(&outputs));
+        let values = outputs.iter()
+            .map(|t| from_token("tokens.next().ok_or(ethabi::Error::InvalidData)?", t, 0))
+            .collect::<Vec<String>>();
+        emit!(out, "            Ok({})", tuple(&values));
+    }
+    emit!(out, "        }}");
+    emit!(out, "    }}");
+    Ok(())
+}
+
+fn generate_event(out: &mut String, event: &Event, name: &str) -> ethabi::Result<()> {
+    let mut fields = Vec::new();
+    let mut data_types = Vec::new();
+    let mut topic = 0;
+    for (i, input) in event.inputs.iter().enumerate() {
+        let param_type = input.param_type()?;
+        let field = param_name(&input.name, i);
+        let hashed = input.indexed && is_hashed(&param_type);
+
+        let (r#type, value) = if hashed {
+            ("ethabi::Hash".to_owned(), format!("topics[{}]", topic))
+        } else if input.indexed {
+            let token = format!("ethabi::decode(&[{}], topics[{}].as_bytes())?.remove(0)", param_type_expr(&param_type), topic);
+            (rust_type(&param_type), from_token(&token, &param_type, 0))
+        } else {
+            (rust_type(&param_type), from_token("data.next().ok_or(ethabi::Error::InvalidData)?", &param_type, 0))
+        };
+
+        if input.indexed {
+            topic += 1;
+        } else {
+            data_types.push(param_type);
+        }
+        fields.push((field, r#type, value));
+    }
+
+    emit!(out, "    /// `{}`", Abi::Event(event.clone()).to_human());
+    emit!(out, "    #[derive(Debug, Clone, PartialEq)]");
+    emit!(out, "    pub struct {} {{", name);
+    for (field, r#type, _) in fields.iter() {
+        emit!(out, "        pub {}: {},", field, r#type);
+    }
+    emit!(out, "    }}");
+    emit!(out);
+    emit!(out, "    impl {} {{", name);
+    emit!(out, "        pub const SIGNATURE: &str = \"{}\";", event.signature()?);
+    emit!(out, "        pub const TOPIC: [u8; 32] = {};", bytes_literal(event.topic()?.as_bytes()));
+    emit!(out);
+    emit!(out, "        pub fn decode(topics: &[ethabi::Hash], data: &[u8]) -> ethabi::Result<Self> {{");
+    if !event.anonymous {
+        emit!(out, "            let topics = match topics.split_first() {{");
+        emit!(out, "                Some((topic, topics)) if topic.as_bytes() == &Self::TOPIC[..] => topics,");
+        emit!(out, "                _ => return Err(ethabi::Error::InvalidData),");
+        emit!(out, "            }};");
+    }
+    emit!(out, "            if topics.len() != {} {{", topic);
+    emit!(out, "                return Err(ethabi::Error::InvalidData);");
+    emit!(out, "            }}");
+    emit!(out, "            let mut data = ethabi::decode(&[{}], data)?.into_iter();", param_types(&data_types));
+    emit!(out, "            Ok(Self {{");
+    for (field, _, value) in fields.iter() {
+        emit!(out, "                {}: {},", field, value);
+    }
+    emit!(out, "            }})");
+    emit!(out, "        }}");
+    emit!(out, "    }}");
+    Ok(())
+}
+
+/// Typed parameters of a generated function
+struct Args {
+    names: Vec<String>,
+    types: Vec<ParamType>,
+}
+
+impl Args {
+    fn new(inputs: &[Variable]) -> ethabi::Result<Self> {
+        Ok(Self {
+            names: inputs.iter().enumerate().map(|(i, v)| param_name(&v.name, i)).collect(),
+            types: inputs.iter().map(Variable::param_type).collect::<ethabi::Result<Vec<ParamType>>>()?,
+        })
+    }
+
+    fn params(&self) -> String {
+        self.names.iter()
+            .zip(self.types.iter())
+            .map(|(name, t)| format!("{}: {}", name, rust_type(t)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn tokens(&self) -> String {
+        self.names.iter()
+            .zip(self.types.iter())
+            .map(|(name, t)| to_token(name, t, 0))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// Name of the module of a contract, also the stem of its file
+pub fn module_name(contract: &str) -> String {
+    ident(contract)
+}
+
+fn ident(name: &str) -> String {
+    let name = snake_case(name);
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn param_name(name: &str, position: usize) -> String {
+    if name.is_empty() {
+        format!("arg{}", position)
+    } else {
+        ident(name)
+    }
+}
+
+fn bytes_literal(bytes: &[u8]) -> String {
+    format!("[{}]", bytes.iter().map(|b| format!("0x{:02x}", b)).collect::<Vec<String>>().join(", "))
+}
+
+fn tuple(items: &[String]) -> String {
+    match items.len() {
+        1 => items[0].clone(),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+fn tuple_type(types: &[ParamType]) -> String {
+    tuple(&types.iter().map(rust_type).collect::<Vec<String>>())
+}
+
+fn param_types(types: &[ParamType]) -> String {
+    types.iter().map(param_type_expr).collect::<Vec<String>>().join(", ")
+}
+
+fn rust_type(param_type: &ParamType) -> String {
+    match param_type {
+        ParamType::Address => "ethabi::Address".to_owned(),
+        ParamType::Uint(_) => "ethabi::Uint".to_owned(),
+        ParamType::Int(_) => "ethabi::Int".to_owned(),
+        ParamType::Bool => "bool".to_owned(),
+        ParamType::String => "String".to_owned(),
+        ParamType::Bytes => "Vec<u8>".to_owned(),
+        ParamType::FixedBytes(len) => format!("[u8; {}]", len),
+        ParamType::Array(t) => format!("Vec<{}>", rust_type(t)),
+        ParamType::FixedArray(t, len) => format!("[{}; {}]", rust_type(t), len),
+        ParamType::Tuple(types) => {
+            let types = types.iter().map(|t| rust_type(t)).collect::<Vec<String>>();
+            if types.len() == 1 {
+                format!("({},)", types[0])
+            } else {
+                format!("({})", types.join(", "))
+            }
+        },
+    }
+}
+
+fn param_type_expr(param_type: &ParamType) -> String {
+    match param_type {
+        ParamType::Address => "ethabi::ParamType::Address".to_owned(),
+        ParamType::Uint(size) => format!("ethabi::ParamType::Uint({})", size),
+        ParamType::Int(size) => format!("ethabi::ParamType::Int({})", size),
+        ParamType::Bool => "ethabi::ParamType::Bool".to_owned(),
+        ParamType::String => "ethabi::ParamType::String".to_owned(),
+        ParamType::Bytes => "ethabi::ParamType::Bytes".to_owned(),
+        ParamType::FixedBytes(len) => format!("ethabi::ParamType::FixedBytes({})", len),
+        ParamType::Array(t) => format!("ethabi::ParamType::Array(Box::new({}))", param_type_expr(t)),
+        ParamType::FixedArray(t, len) => format!("ethabi::ParamType::FixedArray(Box::new({}), {})", param_type_expr(t), len),
+        ParamType::Tuple(types) => format!(
+            "ethabi::ParamType::Tuple(vec![{}])",
+            types.iter().map(|t| format!("Box::new({})", param_type_expr(t))).collect::<Vec<String>>().join(", ")
+        ),
+    }
+}
+
+/// Expression converting `value` of the Rust type of `param_type` into a token
+fn to_token(value: &str, param_type: &ParamType, depth: usize) -> String {
+    let item = format!("v{}", depth);
+    match param_type {
+        ParamType::Address => format!("ethabi::Token::Address({})", value),
+        ParamType::Uint(_) => format!("ethabi::Token::Uint({})", value),
+        ParamType::Int(_) => format!("ethabi::Token::Int({})", value),
+        ParamType::Bool => format!("ethabi::Token::Bool({})", value),
+        ParamType::String => format!("ethabi::Token::String({})", value),
+        ParamType::Bytes => format!("ethabi::Token::Bytes({})", value),
+        ParamType::FixedBytes(_) => format!("ethabi::Token::FixedBytes({}.to_vec())", value),
+        ParamType::Array(t) => format!(
+            "ethabi::Token::Array({}.into_iter().map(|{}| {}).collect())",
+            value, item, to_token(&item, t, depth + 1)
+        ),
+        ParamType::FixedArray(t, _) => format!(
+            "ethabi::Token::FixedArray({}.to_vec().into_iter().map(|{}| {}).collect())",
+            value, item, to_token(&item, t, depth + 1)
+        ),
+        ParamType::Tuple(types) => {
+            let items = (0..types.len()).map(|i| format!("{}_{}", item, i)).collect::<Vec<String>>();
+            let tokens = items.iter()
+                .zip(types.iter())
+                .map(|(item, t)| to_token(item, t, depth + 1))
+                .collect::<Vec<String>>();
+            let pattern = if items.len() == 1 {
+                format!("({},)", items[0])
+            } else {
+                format!("({})", items.join(", "))
+            };
+            format!("{{ let {} = {}; ethabi::Token::Tuple(vec![{}]) }}", pattern, value, tokens.join(", "))
+        },
+    }
+}
+
+/// Expression converting the `token` into the Rust type of `param_type`, to
+/// be used where `?` returns an `ethabi::Result`
+fn from_token(token: &str, param_type: &ParamType, depth: usize) -> String {
+    let item = format!("v{}", depth);
+    let convert = |method: &str| format!("{}.{}().ok_or(ethabi::Error::InvalidData)?", token, method);
+    match param_type {
+        ParamType::Address => convert("to_address"),
+        ParamType::Uint(_) => convert("to_uint"),
+        ParamType::Int(_) => convert("to_int"),
+        ParamType::Bool => convert("to_bool"),
+        ParamType::String => convert("to_string"),
+        ParamType::Bytes => convert("to_bytes"),
+        ParamType::FixedBytes(len) => format!(
+            "<[u8; {}] as std::convert::TryFrom<&[u8]>>::try_from(&{}[..]).map_err(|_| ethabi::Error::InvalidData)?",
+            len, convert("to_fixed_bytes")
+        ),
+        ParamType::Array(t) => format!(
+            "{}.into_iter().map(|{}| Ok({})).collect::<ethabi::Result<Vec<{}>>>()?",
+            convert("to_array"), item, from_token(&item, t, depth + 1), rust_type(t)
+        ),
+        ParamType::FixedArray(t, len) => format!(
+            "<[{t}; {len}] as std::convert::TryFrom<Vec<{t}>>>::try_from({tokens}.into_iter().map(|{item}| Ok({value})).collect::<ethabi::Result<Vec<{t}>>>()?).map_err(|_| ethabi::Error::InvalidData)?",
+            t = rust_type(t),
+            len = len,
+            tokens = convert("to_fixed_array"),
+            item = item,
+            value = from_token(&item, t, depth + 1),
+        ),
+        ParamType::Tuple(types) => {
+            let next = format!("{}.next().ok_or(ethabi::Error::InvalidData)?", item);
+            let values = types.iter()
+                .map(|t| from_token(&next, t, depth + 1))
+                .collect::<Vec<String>>();
+            format!(
+                "match {} {{ ethabi::Token::Tuple({item}) => {{ let mut {item} = {item}.into_iter(); ({},) }}, _ => return Err(ethabi::Error::InvalidData) }}",
+                token, values.join(", "), item = item
+            )
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+
+    fn contract() -> Contract {
+        let abi = human::parse_all("
+            constructor(bytes32[] proposalNames)
+            function owner() view returns (address)
+            function setCompleted(uint256 completed)
+            function safeTransferFrom(address from, address to, uint256 tokenId)
+            function safeTransferFrom(address from, address to, uint256 tokenId, bytes data)
+            function get(uint256 index) view returns ((address owner, uint64[2] times) item, string)
+            event Transfer(address indexed from, address indexed to, uint256 value)
+            event Registered(string indexed name, address owner)
+        ").unwrap();
+        Contract::new("ERC721Token".to_owned(), abi, "6080".to_owned())
+    }
+
+    #[test]
+    fn test_generate_functions() {
+        let code = generate(&contract()).unwrap();
+
+        assert!(code.contains("pub const BYTECODE: &str = \"6080\";"));
+        assert!(code.contains("pub fn deploy_data(proposal_names: Vec<[u8; 32]>) -> Vec<u8> {"));
+
+        assert!(code.contains("    pub mod owner {"));
+        assert!(code.contains("        pub fn decode_output(data: &[u8]) -> ethabi::Result<ethabi::Address> {"));
+
+        assert!(code.contains("    pub mod set_completed {"));
+        assert!(code.contains("        pub const SIGNATURE: &str = \"setCompleted(uint256)\";"));
+        assert!(code.contains("        pub const SELECTOR: [u8; 4] = [0xfd, 0xac, 0xd5, 0x76];"));
+        assert!(code.contains("        pub fn encode_input(completed: ethabi::Uint) -> Vec<u8> {"));
+        assert!(code.contains("        pub fn decode_output(_data: &[u8]) -> ethabi::Result<()> {"));
+
+        assert!(code.contains("    pub mod safe_transfer_from {"));
+        assert!(code.contains("    pub mod safe_transfer_from_1 {"));
+        assert!(code.contains("pub fn encode_input(from: ethabi::Address, to: ethabi::Address, token_id: ethabi::Uint, data: Vec<u8>) -> Vec<u8> {"));
+
+        assert!(code.contains("pub fn decode_output(data: &[u8]) -> ethabi::Result<((ethabi::Address, [ethabi::Uint; 2]), String)> {"));
+    }
+
+    #[test]
+    fn test_generate_events() {
+        let code = generate(&contract()).unwrap();
+
+        assert!(code.contains("    pub struct Transfer {\n        pub from: ethabi::Address,\n        pub to: ethabi::Address,\n        pub value: ethabi::Uint,\n    }"));
+        assert!(code.contains("        pub const SIGNATURE: &str = \"Transfer(address,address,uint256)\";"));
+        assert!(code.contains("            if topics.len() != 2 {"));
+
+        assert!(code.contains("    pub struct Registered {\n        pub name: ethabi::Hash,\n        pub owner: ethabi::Address,\n    }"));
+    }
+
+    /// `tests/bindings.rs` compiles and exercises this file, it has to follow
+    /// any change of the generated code
+    #[test]
+    fn test_generate_fixture() {
+        let code = generate(&contract()).unwrap();
+        assert_eq!(code, include_str!("../../tests/bindings/erc721_token.rs"));
+    }
+
+    #[test]
+    fn test_generate_mod() {
+        assert_eq!(generate_mod(&[contract()]), "//! Generated by tortilla. Do not edit.\n\npub mod erc721_token;\n");
+    }
+}
@@ -0,0 +1,309 @@
+//! TypeScript type definitions, one file per contract. The generated code has
+//! no dependencies, values follow the usual conventions of web3 libraries:
+//!
+//! * `abi` as a `const` literal, so libraries can infer the types from it
+//! * `<Name>Input` and `<Name>Output` interfaces per function, overloads are
+//!   suffixed with their position, e.g. `SafeTransferFrom1Input`
+//! * `<Name>Event` interfaces per event
+//! * one interface per struct, named after its `internalType`
+//! * `Functions` and `Events` mapping the signatures to the above types
+//!
+//! Integers are `bigint`, addresses and bytes are `0x` prefixed hex strings.
+
+use super::{camel_case, is_hashed, unique_names};
+use crate::abi::{Abi, Event, EventVariable, Function, ParamType, Variable};
+use crate::contract::Contract;
+use std::fmt::Write;
+
+macro_rules! emit {
+    ($out:expr) => {
+        writeln!($out).expect("Writing to a String can't fail")
+    };
+    ($out:expr, $($arg:tt)*) => {
+        writeln!($out, $($arg)*).expect("Writing to a String can't fail")
+    };
+}
+
+pub fn generate(contract: &Contract) -> ethabi::Result<String> {
+    let mut structs = Structs::new(&contract.name);
+    let mut items = String::new();
+
+    let constructor = contract.abi.iter().find_map(|a| match a {
+        Abi::Constructor(c) => Some(c),
+        _ => None,
+    });
+    if let Some(constructor) = constructor {
+        emit!(items);
+        emit!(items, "/** `{}` */", Abi::Constructor(constructor.clone()).to_human());
+        generate_interface(&mut items, "ConstructorInput", &constructor.inputs, "arg", &mut structs)?;
+    }
+
+    let functions = contract.abi.iter()
+        .filter_map(|a| match a {
+            Abi::Function(f) => Some(f),
+            _ => None,
+        })
+        .collect::<Vec<&Function>>();
+    let names = unique_names(functions.iter().map(|f| camel_case(&f.name)), "");
+    let mut function_types = Vec::new();
+    for (function, name) in functions.iter().zip(names) {
+        emit!(items);
+        emit!(items, "/** `{}` */", Abi::Function((*function).clone()).to_human());
+        generate_interface(&mut items, &format!("{}Input", name), &function.inputs, "arg", &mut structs)?;
+        emit!(items);
+        generate_interface(&mut items, &format!("{}Output", name), &function.outputs, "output", &mut structs)?;
+        function_types.push((function.signature()?, name));
+    }
+
+    let events = contract.abi.iter()
+        .filter_map(|a| match a {
+            Abi::Event(e) => Some(e),
+            _ => None,
+        })
+        .collect::<Vec<&Event>>();
+    let names = unique_names(events.iter().map(|e| camel_case(&e.name)), "");
+    let mut event_types = Vec::new();
+    for (event, name) in events.iter().zip(names) {
+        emit!(items);
+        emit!(items, "/** `{}` */", Abi::Event((*event).clone()).to_human());
+        generate_event(&mut items, &format!("{}Event", name), event, &mut structs)?;
+        event_types.push((event.signature()?, name));
+    }
+
+    let mut out = String::new();
+    emit!(out, "// Type definitions of the `{}` contract, generated by tortilla. Do not edit.", contract.name);
+    emit!(out);
+    emit!(out, "export const name = \"{}\";", contract.name);
+    emit!(out);
+    emit!(out, "export const abi = {} as const;", serde_json::to_string_pretty(&contract.abi)?);
+    emit!(out);
+    emit!(out, "export const bytecode = \"0x{}\";", contract.bin);
+
+    for (name, fields) in structs.items.iter() {
+        emit!(out);
+        emit!(out, "export interface {} {{", name);
+        for (field, r#type) in fields.iter() {
+            emit!(out, "  {}: {};", field, r#type);
+        }
+        emit!(out, "}}");
+    }
+
+    out.push_str(&items);
+
+    emit!(out);
+    emit!(out, "export interface Functions {{");
+    for (signature, name) in function_types.iter() {
+        emit!(out, "  \"{}\": {{ input: {name}Input; output: {name}Output }};", signature, name = name);
+    }
+    emit!(out, "}}");
+
+    emit!(out);
+    emit!(out, "export interface Events {{");
+    for (signature, name) in event_types.iter() {
+        emit!(out, "  \"{}\": {}Event;", signature, name);
+    }
+    emit!(out, "}}");
+
+    Ok(out)
+}
+
+/// `index.ts` re-exporting the definitions of the contracts
+pub fn generate_index(contracts: &[Contract]) -> String {
+    let mut out = String::new();
+    emit!(out, "// Generated by tortilla. Do not edit.");
+    emit!(out);
+    for contract in contracts.iter() {
+        emit!(out, "export * as {name} from \"./{name}\";", name = contract.name);
+    }
+    out
+}
+
+fn generate_interface(out: &mut String, name: &str, variables: &[Variable], prefix: &str, structs: &mut Structs) -> ethabi::Result<()> {
+    emit!(out, "export interface {} {{", name);
+    for (i, variable) in variables.iter().enumerate() {
+        emit!(out, "  {}: {};", field_name(&variable.name, prefix, i), structs.ts_type(variable)?);
+    }
+    emit!(out, "}}");
+    Ok(())
+}
+
+fn generate_event(out: &mut String, name: &str, event: &Event, structs: &mut Structs) -> ethabi::Result<()> {
+    emit!(out, "export interface {} {{", name);
+    for (i, input) in event.inputs.iter().enumerate() {
+        let hashed = input.indexed && is_hashed(&input.param_type()?);
+        let r#type = if hashed {
+            "string".to_owned()
+        } else {
+            structs.ts_type(&to_variable(input))?
+        };
+        emit!(out, "  {}: {};", field_name(&input.name, "arg", i), r#type);
+    }
+    emit!(out, "}}");
+    Ok(())
+}
+
+/// Interfaces of the structs found while resolving the types, in the order
+/// they need to be declared
+struct Structs<'a> {
+    contract: &'a str,
+    items: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl<'a> Structs<'a> {
+    fn new(contract: &'a str) -> Self {
+        Self { contract, items: Vec::new() }
+    }
+
+    fn ts_type(&mut self, variable: &Variable) -> ethabi::Result<String> {
+        if !variable.r#type.starts_with("tuple") {
+            return Ok(ts_type(&variable.param_type()?));
+        }
+
+        let components = variable.components.as_ref().ok_or(ethabi::Error::InvalidData)?;
+        let mut fields = Vec::new();
+        for (i, component) in components.iter().enumerate() {
+            fields.push((field_name(&component.name, "field", i), self.ts_type(component)?));
+        }
+
+        let base = match variable.internalType.as_ref().and_then(|t| self.struct_name(t)) {
+            Some(name) => {
+                if !self.items.iter().any(|(n, _)| *n == name) {
+                    self.items.push((name.clone(), fields));
+                }
+                name
+            },
+            None => {
+                let fields = fields.iter().map(|(f, t)| format!("{}: {}", f, t)).collect::<Vec<String>>();
+                format!("{{ {} }}", fields.join("; "))
+            },
+        };
+
+        let dimensions = variable.r#type.matches('[').count();
+        Ok(format!("{}{}", base, "[]".repeat(dimensions)))
+    }
+
+    /// `struct Ballot.Voter[]` -> `Voter` in `Ballot`, `Ballot_Voter` elsewhere
+    fn struct_name(&self, internal_type: &str) -> Option<String> {
+        let name = internal_type.strip_prefix("struct ")?;
+        let name = name.split('[').next().unwrap_or(name);
+        Some(match name.split_once('.') {
+            Some((contract, name)) if contract == self.contract => name.to_owned(),
+            _ => name.replace('.', "_"),
+        })
+    }
+}
+
+fn ts_type(param_type: &ParamType) -> String {
+    match param_type {
+        ParamType::Address | ParamType::String | ParamType::Bytes | ParamType::FixedBytes(_) => "string".to_owned(),
+        ParamType::Uint(_) | ParamType::Int(_) => "bigint".to_owned(),
+        ParamType::Bool => "boolean".to_owned(),
+        ParamType::Array(t) | ParamType::FixedArray(t, _) => match **t {
+            ParamType::Tuple(_) => format!("({})[]", ts_type(t)),
+            _ => format!("{}[]", ts_type(t)),
+        },
+        ParamType::Tuple(types) => format!(
+            "[{}]",
+            types.iter().map(|t| ts_type(t)).collect::<Vec<String>>().join(", ")
+        ),
+    }
+}
+
+fn field_name(name: &str, prefix: &str, position: usize) -> String {
+    if name.is_empty() {
+        format!("{}{}", prefix, position)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn to_variable(variable: &EventVariable) -> Variable {
+    Variable {
+        name: variable.name.clone(),
+        r#type: variable.r#type.clone(),
+        components: variable.components.as_ref().map(|c| c.iter().map(to_variable).collect()),
+        internalType: variable.internalType.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract() -> Contract {
+        let abi = Abi::from_json_array(r#"[
+            {"type": "constructor", "stateMutability": "nonpayable", "inputs": [
+                {"name": "proposalNames", "type": "bytes32[]", "internalType": "bytes32[]"}
+            ]},
+            {"type": "function", "name": "voters", "stateMutability": "view",
+                "inputs": [{"name": "", "type": "address", "internalType": "address"}],
+                "outputs": [{"name": "voter", "type": "tuple", "internalType": "struct Ballot.Voter", "components": [
+                    {"name": "weight", "type": "uint256", "internalType": "uint256"},
+                    {"name": "delegate", "type": "address", "internalType": "address"},
+                    {"name": "vote", "type": "tuple", "internalType": "struct Ballot.Vote", "components": [
+                        {"name": "proposal", "type": "uint8", "internalType": "uint8"},
+                        {"name": "cast", "type": "bool", "internalType": "bool"}
+                    ]}
+                ]}]
+            },
+            {"type": "function", "name": "history", "stateMutability": "view", "inputs": [],
+                "outputs": [{"name": "", "type": "tuple[]", "internalType": "struct Registry.Entry[]", "components": [
+                    {"name": "key", "type": "bytes32", "internalType": "bytes32"}
+                ]}]
+            },
+            {"type": "function", "name": "vote", "stateMutability": "nonpayable",
+                "inputs": [{"name": "proposal", "type": "uint256", "internalType": "uint256"}], "outputs": []},
+            {"type": "function", "name": "vote", "stateMutability": "nonpayable",
+                "inputs": [{"name": "proposal", "type": "uint256"}, {"name": "reason", "type": "string"}], "outputs": []},
+            {"type": "event", "name": "Voted", "anonymous": false, "inputs": [
+                {"name": "voter", "type": "address", "indexed": true, "internalType": "address"},
+                {"name": "reason", "type": "string", "indexed": true, "internalType": "string"},
+                {"name": "weight", "type": "uint256", "indexed": false, "internalType": "uint256"}
+            ]}
+        ]"#).unwrap();
+        Contract::new("Ballot".to_owned(), abi, "6080".to_owned())
+    }
+
+    #[test]
+    fn test_generate_structs() {
+        let code = generate(&contract()).unwrap();
+
+        assert!(code.contains("export interface Vote {\n  proposal: bigint;\n  cast: boolean;\n}"));
+        assert!(code.contains("export interface Voter {\n  weight: bigint;\n  delegate: string;\n  vote: Vote;\n}"));
+        assert!(code.find("interface Vote {") < code.find("interface Voter {"));
+        assert!(code.contains("export interface Registry_Entry {\n  key: string;\n}"));
+        assert!(code.contains("export interface HistoryOutput {\n  output0: Registry_Entry[];\n}"));
+    }
+
+    #[test]
+    fn test_generate_functions() {
+        let code = generate(&contract()).unwrap();
+
+        assert!(code.contains("export const abi = [\n"));
+        assert!(code.contains("] as const;"));
+        assert!(code.contains("export const bytecode = \"0x6080\";"));
+        assert!(code.contains("export interface ConstructorInput {\n  proposalNames: string[];\n}"));
+
+        assert!(code.contains("/** `function voters(address) view returns (tuple(uint256 weight, address delegate, tuple(uint8 proposal, bool cast) vote) voter)` */"));
+        assert!(code.contains("export interface VotersInput {\n  arg0: string;\n}"));
+        assert!(code.contains("export interface VotersOutput {\n  voter: Voter;\n}"));
+
+        assert!(code.contains("export interface VoteInput {\n  proposal: bigint;\n}"));
+        assert!(code.contains("export interface Vote1Input {\n  proposal: bigint;\n  reason: string;\n}"));
+        assert!(code.contains("export interface Vote1Output {\n}"));
+        assert!(code.contains("  \"vote(uint256,string)\": { input: Vote1Input; output: Vote1Output };"));
+    }
+
+    #[test]
+    fn test_generate_events() {
+        let code = generate(&contract()).unwrap();
+
+        assert!(code.contains("export interface VotedEvent {\n  voter: string;\n  reason: string;\n  weight: bigint;\n}"));
+        assert!(code.contains("export interface Events {\n  \"Voted(address,string,uint256)\": VotedEvent;\n}"));
+    }
+
+    #[test]
+    fn test_generate_index() {
+        assert_eq!(generate_index(&[contract()]), "// Generated by tortilla. Do not edit.\n\nexport * as Ballot from \"./Ballot\";\n");
+    }
+}
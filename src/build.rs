@@ -1,31 +1,48 @@
 use tortilla::compiler;
-use tortilla::contract::Contract;
+use tortilla::contract::{Address, Contract};
+use tortilla::linker;
 use termion::{color, screen, clear, cursor};
 use super::config::Config;
 use notify::{RecommendedWatcher, Watcher, RecursiveMode, DebouncedEvent};
 use std::sync::mpsc::channel;
 use std::time::Duration;
-use std::io::{Result, stdout, Write};
+use std::io::{Error, Result, stdout, Write};
 use std::path::Path;
+use std::collections::HashMap;
 use chrono::prelude::*;
 
 pub fn build(config: &Config) -> Result<()> {
-    let contracts = compiler::compile_paths(&config.inputs)?;
+    let mut contracts = compiler::compile_paths(&config.inputs)?;
+    if !config.libraries.is_empty() {
+        link_libraries(&mut contracts, &config.libraries);
+    }
+
+    if config.output != "-" {
+        print_compiled_contracts(&contracts, config);
+    }
+    if config.fail_on_size {
+        let oversized = contracts.iter()
+            .filter(|c| c.code_size().exceeds_limits())
+            .map(|c| c.name.as_str())
+            .collect::<Vec<&str>>();
+        if !oversized.is_empty() {
+            return Err(Error::other(format!("Exceeding the size limits: {}", oversized.join(", "))));
+        }
+    }
 
     if config.output == "-" {
         for c in contracts.iter() {
-            if config.pretty_print {
-                println!("{}", c.pretty_print());
-            } else {
-                println!("{}", c);
-            }
+            println!("{}", c.to_json(config.format, config.pretty_print));
         }
-    } else {
-        print_compiled_contracts(&contracts, config.gas);
-        if config.output != "" {
-            for c in contracts.iter() {
-                c.write_to_dir(&config.output, config.pretty_print)?;
+    } else if config.output != "" {
+        for c in contracts.iter() {
+            if let Ok(previous) = Contract::read_from_dir(&config.output, &c.name) {
+                for network in c.outdated_networks(&previous) {
+                    eprintln!("{}Warning: the bytecode of {} changed since it was deployed on network {}{}",
+                        color::Fg(color::Yellow), c.name, network, color::Fg(color::Reset));
+                }
             }
+            c.write_to_dir_as(&config.output, config.format, config.pretty_print)?;
         }
     }
 
@@ -82,26 +99,79 @@ fn try_unwatch_file(watcher: &mut RecommendedWatcher, file: impl AsRef<Path>) ->
     watcher.unwatch(file.as_ref()).is_ok()
 }
 
-fn print_compiled_contracts(contracts: &[Contract], gas_estimates: bool) {
+fn print_compiled_contracts(contracts: &[Contract], config: &Config) {
     let local = Local::now();
     for c in contracts.iter() {
+        let size = c.code_size();
+        let name_color = if size.exceeds_limits() {
+            color::Fg(color::Red).to_string()
+        } else if size.is_close_to_limits() {
+            color::Fg(color::Yellow).to_string()
+        } else {
+            color::Fg(color::Green).to_string()
+        };
         println!("[{}] {}{} compiled{}",
             local.format("%Y-%m-%d %H:%M:%S").to_string(),
-            color::Fg(color::Green),
+            name_color,
             c.name,
             color::Fg(color::Reset)
         );
-        if gas_estimates {
+        if config.gas {
             println!("{}", c.gas_estimates_to_string());
         }
+        if config.sizes {
+            println!("{}", c.code_size_to_string());
+        }
+        if size.exceeds_limits() {
+            print_warning(&format!("{} exceeds the size limits of EIP-170 or EIP-3860", c.name));
+        } else if size.is_close_to_limits() {
+            print_warning(&format!("{} is close to the size limits of EIP-170 or EIP-3860", c.name));
+        }
+        if !c.link_references.is_empty() {
+            let names = c.link_references.iter().map(|r| r.name.as_str()).collect::<Vec<&str>>();
+            print_warning(&format!("{} needs to be linked against {}, see --libraries", c.name, names.join(", ")));
+        }
+        for clash in c.selector_clashes() {
+            print_warning(&clash.to_string());
+        }
+    }
+
+    for (proxy, implementation) in config.proxies.iter() {
+        let find = |name: &String| contracts.iter().find(|c| &c.name == name);
+        match (find(proxy), find(implementation)) {
+            (Some(proxy), Some(implementation)) => {
+                for clash in proxy.proxy_clashes(implementation) {
+                    print_warning(&clash.to_string());
+                }
+            },
+            _ => print_warning(&format!("{}:{} not found among the compiled contracts", proxy, implementation)),
+        }
     }
 }
 
-pub fn build_to_stderr(config: &Config, clear_screen: bool) {
+/// Links the given libraries, leaving the placeholders of the others
+fn link_libraries(contracts: &mut [Contract], libraries: &HashMap<String, Address>) {
+    for c in contracts.iter_mut() {
+        let (bin, unresolved) = linker::link(&c.bin, &c.link_references, libraries);
+        let (bin_runtime, _) = linker::link(&c.bin_runtime, &c.link_references, libraries);
+        c.link_references = unresolved.into_iter().cloned().collect();
+        c.bin = bin;
+        c.bin_runtime = bin_runtime;
+    }
+}
+
+fn print_warning(warning: &str) {
+    println!("{}Warning: {}{}", color::Fg(color::Yellow), warning, color::Fg(color::Reset));
+}
+
+/// Whether the build succeeded, errors are printed to stderr
+pub fn build_to_stderr(config: &Config, clear_screen: bool) -> bool {
     if clear_screen {
         restart_screen().unwrap();
     }
     if let Err(e) = build(&config) {
         eprintln!("{}{}", color::Fg(color::Red), e);
+        return false;
     }
+    true
 }
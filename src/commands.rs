@@ -0,0 +1,15 @@
+//! Subcommands of the binary, each one exposes its clap definition and a `run`
+//! taking the matches of the subcommand.
+
+pub mod abi;
+pub mod abi_diff;
+pub mod bindgen;
+pub mod call;
+pub mod deploy;
+pub mod disasm;
+pub mod gas;
+pub mod migrate;
+pub mod send;
+#[cfg(feature = "evm")]
+pub mod test;
+pub mod verify_input;
@@ -0,0 +1,46 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use tortilla::abi::human;
+use tortilla::compiler;
+use std::io::Result;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("abi")
+        .about("Prints the ABI of the contracts")
+        .arg(Arg::with_name("INPUTS")
+             .help("Sets the input files/dirs to use")
+             .required(true)
+             .multiple(true))
+        .arg(Arg::with_name("HUMAN")
+             .long("human")
+             .help("Prints the human-readable ABI instead of JSON"))
+        .arg(Arg::with_name("PRETTY_PRINT")
+             .short("p")
+             .long("pretty")
+             .help("Sets the JSON to be pretty printed"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let inputs = crate::filter_paths(matches.values_of_lossy("INPUTS").unwrap());
+    if inputs.is_empty() {
+        std::process::exit(1);
+    }
+
+    let contracts = compiler::compile_paths(&inputs)?;
+    for c in contracts.iter() {
+        if contracts.len() > 1 {
+            println!("======= {} =======", c.name);
+        }
+
+        if matches.is_present("HUMAN") {
+            for line in human::format_all(&c.abi) {
+                println!("{}", line);
+            }
+        } else if matches.is_present("PRETTY_PRINT") {
+            println!("{}", serde_json::to_string_pretty(&c.abi).unwrap());
+        } else {
+            println!("{}", serde_json::to_string(&c.abi).unwrap());
+        }
+    }
+
+    Ok(())
+}
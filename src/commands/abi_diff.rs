@@ -0,0 +1,51 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use tortilla::abi::{Abi, diff::{self, ChangeKind}};
+use tortilla::contract::Contract;
+use termion::color;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("abi-diff")
+        .about("Reports the changes between two versions of an ABI, exits with 1 on breaking changes")
+        .arg(Arg::with_name("OLD")
+             .help("Sets the old artifact or JSON ABI")
+             .required(true))
+        .arg(Arg::with_name("NEW")
+             .help("Sets the new artifact or JSON ABI")
+             .required(true))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let old = read_abi(matches.value_of("OLD").unwrap())?;
+    let new = read_abi(matches.value_of("NEW").unwrap())?;
+
+    let diff = diff::diff(&old, &new);
+    for change in diff.changes.iter() {
+        let color: &dyn color::Color = match change.kind {
+            _ if change.breaking => &color::Red,
+            ChangeKind::Added => &color::Green,
+            ChangeKind::Removed | ChangeKind::Changed => &color::Yellow,
+        };
+        println!("{}{}{}", color::Fg(color), change, color::Fg(color::Reset));
+    }
+
+    if diff.is_breaking() {
+        eprintln!("{}{} breaking change(s){}", color::Fg(color::Red), diff.breaking_changes().count(), color::Fg(color::Reset));
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Reads either an artifact, in any of the supported formats, or a bare JSON
+/// ABI array
+pub fn read_abi(path: &str) -> Result<Vec<Abi>> {
+    Contract::read_from_file(path)
+        .map(|c| c.abi)
+        .or_else(|_| {
+            let json = fs::read_to_string(path)?;
+            Abi::from_json_array(&json)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, e)))
+        })
+}
@@ -0,0 +1,40 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use tortilla::bindgen::{self, Lang};
+use tortilla::compiler;
+use std::io::Result;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("bindgen")
+        .about("Generates typed bindings for the contracts")
+        .arg(Arg::with_name("INPUTS")
+             .help("Sets the input files/dirs to use")
+             .required(true)
+             .multiple(true))
+        .arg(Arg::with_name("LANG")
+             .long("lang")
+             .takes_value(true)
+             .possible_values(&["rust", "typescript", "ts"])
+             .default_value("rust")
+             .help("Sets the language of the bindings"))
+        .arg(Arg::with_name("OUTPUT")
+             .short("o")
+             .long("output")
+             .takes_value(true)
+             .required(true)
+             .help("Sets the output directory"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let inputs = crate::filter_paths(matches.values_of_lossy("INPUTS").unwrap());
+    if inputs.is_empty() {
+        std::process::exit(1);
+    }
+
+    let lang: Lang = matches.value_of("LANG").unwrap().parse().unwrap();
+    let contracts = compiler::compile_paths(&inputs)?;
+    for file in bindgen::write_to_dir(&contracts, lang, matches.value_of("OUTPUT").unwrap())? {
+        println!("{}", file.display());
+    }
+
+    Ok(())
+}
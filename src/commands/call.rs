@@ -0,0 +1,98 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use super::deploy::{self, node_args, parse_address};
+use tortilla::abi::{self, Function};
+use tortilla::contract::{Address, Contract};
+use tortilla::rpc::{Client, TransactionRequest};
+use std::io::{Error, ErrorKind, Result};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("call")
+        .about("Calls a function of a deployed contract without sending a transaction and prints its outputs")
+        .args(&target_args())
+        .args(&node_args())
+        .arg(Arg::with_name("FROM")
+             .long("from")
+             .takes_value(true)
+             .validator(|a| parse_address(&a).map(|_| ()))
+             .help("Sets the sender of the call"))
+}
+
+/// Contract, function and arguments shared with `send`
+pub fn target_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("CONTRACT")
+            .help("Sets the contract, by name in the artifacts dir or as the path of its artifact")
+            .required(true),
+        Arg::with_name("FUNCTION")
+            .help("Sets the function, by name or by signature when it's overloaded")
+            .required(true),
+        Arg::with_name("ARGS")
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .help("Sets the arguments of the function"),
+        Arg::with_name("ADDRESS")
+            .long("address")
+            .takes_value(true)
+            .validator(|a| parse_address(&a).map(|_| ()))
+            .help("Sets the address of the contract instead of the one deployed on the network of the node"),
+    ]
+}
+
+/// Function of a deployed contract and the calldata of the call
+pub struct Target {
+    pub contract: Contract,
+    pub address: Address,
+    pub function: Function,
+    pub calldata: Vec<u8>,
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let client = Client::new(matches.value_of("RPC").unwrap())?;
+    let Target { address, function, calldata, .. } = target(&client, matches)?;
+
+    let output = client.call(&TransactionRequest {
+        from: matches.value_of("FROM").map(|from| parse_address(from).unwrap()),
+        to: Some(address),
+        data: calldata,
+        ..TransactionRequest::default()
+    })?;
+    let tokens = function.decode_output(&output)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Couldn't decode the output of {}: {}", function.name, e)))?;
+
+    for (output, token) in function.outputs.iter().zip(tokens.iter()) {
+        if output.name.is_empty() {
+            println!("{}", abi::format_token(token));
+        } else {
+            println!("{}: {}", output.name, abi::format_token(token));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the artifact, encodes the call and looks up the address of the
+/// contract on the network of the node unless `--address` is given
+pub fn target(client: &Client, matches: &ArgMatches) -> Result<Target> {
+    let (_, contract) = deploy::read_artifact(matches)?;
+    let values = matches.values_of_lossy("ARGS").unwrap_or_default();
+
+    let function = contract.find_function(matches.value_of("FUNCTION").unwrap(), values.len())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?
+        .clone();
+    let calldata = abi::tokenize(&function.inputs, &values)
+        .and_then(|tokens| function.encode_input(&tokens))
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid arguments of {}: {}", function.name, e)))?;
+
+    let address = match matches.value_of("ADDRESS") {
+        Some(address) => parse_address(address).unwrap(),
+        None => {
+            let net_version = client.net_version()?;
+            contract.get_address(&net_version).ok_or_else(|| Error::new(
+                ErrorKind::NotFound,
+                format!("{} isn't deployed on network {}, deploy it or give its --address", contract.name, net_version),
+            ))?
+        },
+    };
+
+    Ok(Target { contract, address, function, calldata })
+}
@@ -0,0 +1,182 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use ethereum_types::U256;
+use termion::color;
+use termion::input::TermRead;
+use tortilla::abi::{self, Abi};
+use tortilla::contract::{Address, Contract};
+use tortilla::deploy::Deployer;
+use tortilla::linker;
+use tortilla::rpc::Client;
+use tortilla::wallet::Wallet;
+use std::fs;
+use std::io::{self, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("deploy")
+        .about("Deploys a compiled contract and records its address in the artifact")
+        .arg(Arg::with_name("CONTRACT")
+             .help("Sets the contract, by name in the artifacts dir or as the path of its artifact")
+             .required(true))
+        .arg(Arg::with_name("ARGS")
+             .long("args")
+             .takes_value(true)
+             .multiple(true)
+             .allow_hyphen_values(true)
+             .help("Sets the arguments of the constructor"))
+        .args(&node_args())
+        .args(&sender_args())
+        .arg(Arg::with_name("GAS")
+             .long("gas")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas limit, estimated by the node by default"))
+        .arg(Arg::with_name("GAS_PRICE")
+             .long("gas-price")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas price in wei"))
+        .arg(Arg::with_name("VALUE")
+             .long("value")
+             .takes_value(true)
+             .validator(|v| parse_u256(&v).map(|_| ()))
+             .help("Sets the wei sent to the constructor"))
+        .arg(Arg::with_name("LIBRARIES")
+             .long("libraries")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .value_name("NAME=ADDRESS")
+             .validator(|l| linker::parse_library(&l).map(|_| ()))
+             .help("Links the library NAME deployed at ADDRESS"))
+}
+
+/// Node and artifacts arguments shared by the subcommands talking to a node
+pub fn node_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("RPC")
+            .long("rpc")
+            .takes_value(true)
+            .default_value("http://127.0.0.1:8545")
+            .help("Sets the JSON-RPC endpoint of the node"),
+        Arg::with_name("ARTIFACTS")
+            .long("artifacts")
+            .takes_value(true)
+            .default_value("build")
+            .help("Sets the dir of the artifacts"),
+    ]
+}
+
+/// Account arguments shared by the subcommands sending transactions
+pub fn sender_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("FROM")
+            .long("from")
+            .takes_value(true)
+            .validator(|a| parse_address(&a).map(|_| ()))
+            .conflicts_with("KEYSTORE")
+            .help("Sends from an account unlocked in the node, the first one by default"),
+        Arg::with_name("KEYSTORE")
+            .long("keystore")
+            .takes_value(true)
+            .help("Signs the transactions with the key of a JSON keystore"),
+        Arg::with_name("PASSWORD_FILE")
+            .long("password-file")
+            .takes_value(true)
+            .requires("KEYSTORE")
+            .help("Reads the password of the keystore from a file instead of TORTILLA_PASSWORD or a prompt"),
+    ]
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let (path, mut contract) = read_artifact(matches)?;
+    let client = Client::new(matches.value_of("RPC").unwrap())?;
+
+    let constructor = contract.abi.iter().find_map(|abi| match abi {
+        Abi::Constructor(constructor) => Some(constructor.inputs.clone()),
+        _ => None,
+    });
+    let values = matches.values_of_lossy("ARGS").unwrap_or_default();
+    let args = abi::tokenize(&constructor.unwrap_or_default(), &values)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid arguments of the constructor: {}", e)))?;
+
+    let mut deployer = deployer(&client, matches)?;
+    if let Some(gas) = matches.value_of("GAS") {
+        deployer = deployer.gas(parse_u256(gas).unwrap());
+    }
+    if let Some(gas_price) = matches.value_of("GAS_PRICE") {
+        deployer = deployer.gas_price(parse_u256(gas_price).unwrap());
+    }
+    if let Some(value) = matches.value_of("VALUE") {
+        deployer = deployer.value(parse_u256(value).unwrap());
+    }
+    if let Some(libraries) = matches.values_of("LIBRARIES") {
+        deployer = deployer.libraries(libraries.map(|l| linker::parse_library(l).unwrap()).collect());
+    }
+
+    let deployment = deployer.deploy(&mut contract, &args)?;
+    contract.update_file(&path)?;
+
+    println!("{}{} deployed at 0x{:x} on network {}{}",
+        color::Fg(color::Green), contract.name, deployment.network.address(), deployment.net_version, color::Fg(color::Reset));
+    println!("transaction: 0x{:x}", deployment.receipt.transaction_hash);
+    if let Some(gas_used) = deployment.receipt.gas_used {
+        println!("   gas used: {}", gas_used);
+    }
+
+    Ok(())
+}
+
+/// Deployer sending from `--from` or `--keystore`
+pub fn deployer<'a>(client: &'a Client, matches: &ArgMatches) -> Result<Deployer<'a>> {
+    let deployer = Deployer::new(client);
+    if let Some(keystore) = matches.value_of("KEYSTORE") {
+        let password = match matches.value_of("PASSWORD_FILE") {
+            Some(file) => fs::read_to_string(file)?.trim_end_matches(&['\r', '\n'][..]).to_owned(),
+            None => match std::env::var("TORTILLA_PASSWORD") {
+                Ok(password) => password,
+                Err(_) => read_password(keystore)?,
+            },
+        };
+        Ok(deployer.wallet(Wallet::from_keystore(&fs::read_to_string(keystore)?, &password)?))
+    } else if let Some(from) = matches.value_of("FROM") {
+        Ok(deployer.from(parse_address(from).unwrap()))
+    } else {
+        Ok(deployer)
+    }
+}
+
+/// Path and contract of the artifact given as `CONTRACT`
+pub fn read_artifact(matches: &ArgMatches) -> Result<(PathBuf, Contract)> {
+    let contract = matches.value_of("CONTRACT").unwrap();
+    let path = if contract.ends_with(".json") && Path::new(contract).exists() {
+        PathBuf::from(contract)
+    } else {
+        Path::new(matches.value_of("ARTIFACTS").unwrap()).join(format!("{}.json", contract))
+    };
+
+    let contract = Contract::read_from_file(&path)
+        .map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    Ok((path, contract))
+}
+
+fn read_password(keystore: &str) -> Result<String> {
+    let mut stderr = io::stderr();
+    write!(stderr, "Password of {}: ", keystore)?;
+    stderr.flush()?;
+    let password = io::stdin().read_passwd(&mut stderr)?;
+    writeln!(stderr)?;
+    password.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No password given"))
+}
+
+/// Decimal or `0x` prefixed hex
+pub fn parse_u256(value: &str) -> std::result::Result<U256, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => hex.parse::<U256>().map_err(|_| format!("Invalid number: {}", value)),
+        None => U256::from_dec_str(value).map_err(|_| format!("Invalid number: {}", value)),
+    }
+}
+
+pub fn parse_address(address: &str) -> std::result::Result<Address, String> {
+    address.trim_start_matches("0x").parse().map_err(|_| format!("Invalid address: {}", address))
+}
@@ -0,0 +1,30 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use tortilla::contract::Contract;
+use tortilla::disasm;
+use std::io::{Error, ErrorKind, Result};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("disasm")
+        .about("Prints the opcodes of a compiled contract")
+        .arg(Arg::with_name("ARTIFACT")
+             .help("Sets the JSON artifact of the contract, in any of the supported formats")
+             .required(true))
+        .arg(Arg::with_name("RUNTIME")
+             .long("runtime")
+             .help("Disassembles the runtime bytecode instead of the creation one"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let contract = Contract::read_from_file(matches.value_of("ARTIFACT").unwrap())?;
+
+    let bin = if matches.is_present("RUNTIME") { &contract.bin_runtime } else { &contract.bin };
+    if bin.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, format!("{} has no bytecode", contract.name)));
+    }
+
+    let disassembly = disasm::disassemble(bin)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    print!("{}", disasm::format(&disassembly, &contract.selectors()));
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use termion::color;
+use tortilla::compiler;
+use tortilla::contract::Contract;
+use tortilla::gas::{GasReport, Snapshot};
+#[cfg(feature = "evm")]
+use tortilla::testing;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    let inputs = Arg::with_name("INPUTS")
+        .help("Sets the input files/dirs to use")
+        .required(true)
+        .multiple(true);
+    let snapshot = Arg::with_name("SNAPSHOT")
+        .long("snapshot")
+        .takes_value(true)
+        .default_value(".gas-snapshot")
+        .help("Sets the snapshot file");
+
+    let snapshot_command = SubCommand::with_name("snapshot")
+        .about("Writes the gas estimates of every function to the snapshot file")
+        .arg(inputs.clone())
+        .arg(snapshot.clone());
+    let check_command = SubCommand::with_name("check")
+        .about("Compares the gas estimates with the snapshot file, exits with 1 on increases above the threshold")
+        .arg(inputs.clone())
+        .arg(snapshot)
+        .arg(Arg::with_name("THRESHOLD")
+             .long("threshold")
+             .takes_value(true)
+             .default_value("0")
+             .validator(|t| t.parse::<f64>().map(|_| ()).map_err(|_| format!("Invalid percentage: {}", t)))
+             .help("Sets the increase allowed, in percent"));
+    #[cfg(feature = "evm")]
+    let (snapshot_command, check_command) = {
+        let measure = Arg::with_name("MEASURE")
+            .long("measure")
+            .help("Uses the gas of the Solidity tests run on the in-process EVM instead of the estimates of solc");
+        (snapshot_command.arg(measure.clone()), check_command.arg(measure))
+    };
+
+    SubCommand::with_name("gas")
+        .about("Snapshots and reports of the gas estimates")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(snapshot_command)
+        .subcommand(check_command)
+        .subcommand(SubCommand::with_name("report")
+            .about("Prints the gas estimates of every function as a table")
+            .arg(inputs)
+            .arg(Arg::with_name("FORMAT")
+                 .long("format")
+                 .takes_value(true)
+                 .possible_values(&["markdown", "csv", "json"])
+                 .default_value("markdown")
+                 .help("Sets the format of the report"))
+            .arg(Arg::with_name("CONTRACT")
+                 .long("contract")
+                 .takes_value(true)
+                 .multiple(true)
+                 .number_of_values(1)
+                 .help("Reports only the given contract"))
+            .arg(Arg::with_name("PRETTY_PRINT")
+                 .short("p")
+                 .long("pretty")
+                 .help("Sets the JSON to be pretty printed")))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        ("snapshot", Some(matches)) => snapshot(matches),
+        ("check", Some(matches)) => check(matches),
+        ("report", Some(matches)) => report(matches),
+        _ => unreachable!("A subcommand is required"),
+    }
+}
+
+fn snapshot(matches: &ArgMatches) -> Result<()> {
+    let snapshot = take_snapshot(matches)?;
+    let path = matches.value_of("SNAPSHOT").unwrap();
+    fs::write(path, snapshot.to_string())?;
+    eprintln!("{} estimates written to {}", snapshot.entries.len(), path);
+    Ok(())
+}
+
+fn check(matches: &ArgMatches) -> Result<()> {
+    let path = matches.value_of("SNAPSHOT").unwrap();
+    let before: Snapshot = fs::read_to_string(path)?
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, e)))?;
+    let threshold: f64 = matches.value_of("THRESHOLD").unwrap().parse().unwrap();
+
+    let changes = take_snapshot(matches)?.compare(&before);
+    for change in changes.iter() {
+        let color: &dyn color::Color = match change.percent() {
+            _ if change.exceeds(threshold) => &color::Red,
+            Some(percent) if percent < 0.0 => &color::Green,
+            _ => &color::Yellow,
+        };
+        println!("{}{}{}", color::Fg(color), change, color::Fg(color::Reset));
+    }
+
+    let regressions = changes.iter().filter(|c| c.exceeds(threshold)).count();
+    if regressions > 0 {
+        eprintln!("{}{} estimate(s) increased more than {}%{}", color::Fg(color::Red), regressions, threshold, color::Fg(color::Reset));
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn report(matches: &ArgMatches) -> Result<()> {
+    let mut report = GasReport::new(&compile(matches)?);
+    if let Some(contracts) = matches.values_of_lossy("CONTRACT") {
+        report = report.filter(&contracts);
+    }
+
+    match matches.value_of("FORMAT").unwrap() {
+        "csv" => print!("{}", report.to_csv()),
+        "json" => println!("{}", report.to_json(matches.is_present("PRETTY_PRINT"))),
+        _ => print!("{}", report.to_markdown()),
+    }
+
+    Ok(())
+}
+
+/// Estimates of the contracts, or the gas used by their tests with `--measure`
+fn take_snapshot(matches: &ArgMatches) -> Result<Snapshot> {
+    let contracts = compile(matches)?;
+    #[cfg(feature = "evm")]
+    {
+        if matches.is_present("MEASURE") {
+            let results = testing::run(&contracts, None);
+            let failed = results.iter().filter(|r| !r.passed()).count();
+            if failed > 0 {
+                return Err(Error::other(format!("{} test(s) failed, see tortilla test", failed)));
+            }
+            return Ok(Snapshot::from_tests(&results));
+        }
+    }
+    Ok(Snapshot::from_contracts(&contracts))
+}
+
+fn compile(matches: &ArgMatches) -> Result<Vec<Contract>> {
+    let inputs = crate::filter_paths(matches.values_of_lossy("INPUTS").unwrap());
+    if inputs.is_empty() {
+        std::process::exit(1);
+    }
+
+    compiler::compile_paths(&inputs)
+}
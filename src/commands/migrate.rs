@@ -0,0 +1,64 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use super::deploy::{self, node_args, parse_u256, sender_args};
+use termion::color;
+use tortilla::migrate::{Migration, Migrator};
+use tortilla::rpc::Client;
+use std::io::Result;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("migrate")
+        .about("Runs the migrations after the last one recorded in the Migrations contract")
+        .arg(Arg::with_name("MIGRATIONS")
+             .help("Sets the dir of the numbered migrations, e.g. 1_initial.toml")
+             .default_value("migrations"))
+        .args(&node_args())
+        .args(&sender_args())
+        .arg(Arg::with_name("GAS")
+             .long("gas")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas limit of every transaction, estimated by the node by default"))
+        .arg(Arg::with_name("GAS_PRICE")
+             .long("gas-price")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas price in wei"))
+        .arg(Arg::with_name("DRY_RUN")
+             .long("dry-run")
+             .help("Runs on a snapshot of a development node that is reverted, without writing the artifacts"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let migrations = Migration::read_dir(matches.value_of("MIGRATIONS").unwrap())?;
+    let client = Client::new(matches.value_of("RPC").unwrap())?;
+
+    let mut deployer = deploy::deployer(&client, matches)?;
+    if let Some(gas) = matches.value_of("GAS") {
+        deployer = deployer.gas(parse_u256(gas).unwrap());
+    }
+    if let Some(gas_price) = matches.value_of("GAS_PRICE") {
+        deployer = deployer.gas_price(parse_u256(gas_price).unwrap());
+    }
+
+    let dry_run = matches.is_present("DRY_RUN");
+    let reports = Migrator::new(&client, deployer, matches.value_of("ARTIFACTS").unwrap())
+        .dry_run(dry_run)
+        .run(&migrations)?;
+
+    if reports.is_empty() {
+        println!("Nothing to migrate");
+    }
+    for report in reports.iter() {
+        println!("{}{} {}{}", color::Fg(color::Green), report.number, report.name, color::Fg(color::Reset));
+        for (name, address) in report.deployed.iter() {
+            println!("  {} deployed at 0x{:x}", name, address);
+        }
+        println!("  {} transaction(s)", report.transactions.len());
+    }
+    if dry_run {
+        println!("{}Dry run, the chain was reverted and the artifacts weren't written{}",
+            color::Fg(color::Yellow), color::Fg(color::Reset));
+    }
+
+    Ok(())
+}
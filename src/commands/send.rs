@@ -0,0 +1,72 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use super::call::{self, target_args};
+use super::deploy::{self, node_args, parse_u256, sender_args};
+use termion::color;
+use tortilla::abi::{self, DecodedValue, Log};
+use tortilla::rpc::Client;
+use std::io::Result;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("send")
+        .about("Sends a transaction calling a function of a deployed contract and prints its events")
+        .args(&target_args())
+        .args(&node_args())
+        .args(&sender_args())
+        .arg(Arg::with_name("GAS")
+             .long("gas")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas limit, estimated by the node by default"))
+        .arg(Arg::with_name("GAS_PRICE")
+             .long("gas-price")
+             .takes_value(true)
+             .validator(|g| parse_u256(&g).map(|_| ()))
+             .help("Sets the gas price in wei"))
+        .arg(Arg::with_name("VALUE")
+             .long("value")
+             .takes_value(true)
+             .validator(|v| parse_u256(&v).map(|_| ()))
+             .help("Sets the wei sent to a payable function"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let client = Client::new(matches.value_of("RPC").unwrap())?;
+    let target = call::target(&client, matches)?;
+
+    let mut sender = deploy::deployer(&client, matches)?;
+    if let Some(gas) = matches.value_of("GAS") {
+        sender = sender.gas(parse_u256(gas).unwrap());
+    }
+    if let Some(gas_price) = matches.value_of("GAS_PRICE") {
+        sender = sender.gas_price(parse_u256(gas_price).unwrap());
+    }
+    if let Some(value) = matches.value_of("VALUE") {
+        sender = sender.value(parse_u256(value).unwrap());
+    }
+
+    let receipt = sender.transact(target.address, target.calldata)?;
+    println!("{}transaction: 0x{:x}{}", color::Fg(color::Green), receipt.transaction_hash, color::Fg(color::Reset));
+    println!("      block: {}", receipt.block_number);
+    if let Some(gas_used) = receipt.gas_used {
+        println!("   gas used: {}", gas_used);
+    }
+
+    for log in receipt.logs.iter() {
+        let decoded = match log.address == target.address {
+            true => target.contract.decode_log(&Log { topics: log.topics.clone(), data: log.data.clone() }).ok(),
+            false => None,
+        };
+        match decoded {
+            Some(event) => println!("{}({})", event.name, event.params.iter()
+                .map(|p| match &p.value {
+                    DecodedValue::Token(token) => format!("{}: {}", p.name, abi::format_token(token)),
+                    DecodedValue::Hashed(hash) => format!("{}: 0x{:x} (hashed)", p.name, hash),
+                })
+                .collect::<Vec<String>>()
+                .join(", ")),
+            None => println!("log of 0x{:x} with {} topic(s)", log.address, log.topics.len()),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use termion::color;
+use tortilla::compiler;
+use tortilla::testing;
+use std::io::Result;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("test")
+        .about("Runs the test functions of the Solidity contracts on an in-process EVM")
+        .arg(Arg::with_name("INPUTS")
+             .help("Sets the input files/dirs of the tests")
+             .multiple(true)
+             .default_value("test"))
+        .arg(Arg::with_name("MATCH")
+             .long("match")
+             .takes_value(true)
+             .help("Runs only the tests whose name contains the pattern"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let inputs = crate::filter_paths(matches.values_of_lossy("INPUTS").unwrap());
+    if inputs.is_empty() {
+        std::process::exit(1);
+    }
+
+    let contracts = compiler::compile_paths(&inputs)?;
+    let results = testing::run(&contracts, matches.value_of("MATCH"));
+
+    let mut contract = None;
+    for result in results.iter() {
+        if contract != Some(&result.contract) {
+            println!("{}", result.contract);
+            contract = Some(&result.contract);
+        }
+        let gas = result.gas_used.map(|gas| format!(" (gas: {})", gas)).unwrap_or_default();
+        match &result.failure {
+            None => println!("  {}[PASS]{} {}{}", color::Fg(color::Green), color::Fg(color::Reset), result.test, gas),
+            Some(failure) => println!("  {}[FAIL]{} {}{}: {}",
+                color::Fg(color::Red), color::Fg(color::Reset), result.test, gas, failure),
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    println!("{} passed, {} failed", results.len() - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use termion::color;
+use tortilla::compiler;
+use tortilla::verify;
+use std::io::{Error, ErrorKind, Result};
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("verify-input")
+        .about("Prints the standard JSON input reproducing the bytecode of a contract")
+        .arg(Arg::with_name("CONTRACT")
+             .help("Sets the contract, as FILE:NAME")
+             .required(true))
+        .arg(Arg::with_name("STANDARD_JSON")
+             .long("standard-json")
+             .help("Prints only the input for solc, without the compiler version and contract name"))
+        .arg(Arg::with_name("VERIFY")
+             .long("verify")
+             .help("Recompiles the input and compares the runtime bytecode, ignoring the metadata"))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let contract = matches.value_of("CONTRACT").unwrap();
+    let (path, name) = match contract.rsplit_once(':') {
+        Some((path, name)) if !path.is_empty() && !name.is_empty() => (path, name),
+        _ => {
+            eprintln!("Expected FILE:NAME, got {}", contract);
+            std::process::exit(1);
+        },
+    };
+
+    let contracts = compiler::compile_file(path)?;
+    let contract = contracts.iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("{} not found in {}", name, path)))?;
+    let input = verify::verify_input(contract)?;
+
+    if matches.is_present("VERIFY") {
+        if let Some(version) = compiler::version() {
+            if compiler::release(&version) != compiler::release(&input.compiler_version) {
+                eprintln!("{}Warning: compiled with {}, the local solc is {}{}",
+                    color::Fg(color::Yellow), input.compiler_version, version, color::Fg(color::Reset));
+            }
+        }
+
+        if verify::verify(contract, &input)? {
+            println!("{}{} verified{}", color::Fg(color::Green), input.contract_name, color::Fg(color::Reset));
+        } else {
+            println!("{}{} doesn't match its input{}", color::Fg(color::Red), input.contract_name, color::Fg(color::Reset));
+            std::process::exit(1);
+        }
+    } else if matches.is_present("STANDARD_JSON") {
+        println!("{}", serde_json::to_string_pretty(&input.input)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&input)?);
+    }
+
+    Ok(())
+}
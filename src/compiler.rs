@@ -4,6 +4,8 @@ use std::io::Result;
 use std::path::Path;
 use std::fs::{read_dir};
 
+pub use crate::solc::{release, version};
+
 macro_rules! solc_to_contracts {
     ($expr:expr) => {
         $expr.into_iter()
@@ -15,16 +17,47 @@ macro_rules! solc_to_contracts {
 pub fn compile_str(contract: &str) -> Result<Vec<Contract>> {
     let contracts = compile_contract(CompilerInput::Stdin(contract))?;
 
-    Ok(solc_to_contracts!(contracts))
+    let mut contracts: Vec<Contract> = solc_to_contracts!(contracts);
+    let compiler_version = version();
+    for c in contracts.iter_mut() {
+        c.compiler_version = compiler_version.clone();
+    }
+    Ok(contracts)
 }
 
 pub fn compile_file(file: impl AsRef<Path>) -> Result<Vec<Contract>> {
+    compile_file_with(file, &version())
+}
+
+pub fn compile_dir(dir: impl AsRef<Path>) -> Result<Vec<Contract>> {
+    compile_dir_with(dir, &version())
+}
+
+pub fn compile_path(path: impl AsRef<Path>) -> Result<Vec<Contract>> {
+    compile_path_with(path, &version())
+}
+
+/// `solc --version` runs once for all the paths
+pub fn compile_paths(paths: &[impl AsRef<Path>]) -> Result<Vec<Contract>> {
+    let compiler_version = version();
+    paths.iter()
+        .map(|path| compile_path_with(path, &compiler_version))
+        .collect::<Result<Vec<Vec<Contract>>>>()
+        .and_then(|c| Ok(c.into_iter().flatten().collect()))
+}
+
+fn compile_file_with(file: impl AsRef<Path>, compiler_version: &Option<String>) -> Result<Vec<Contract>> {
     let contracts = compile_contract(CompilerInput::Path(file.as_ref()))?;
 
-    Ok(solc_to_contracts!(contracts))
+    let mut contracts: Vec<Contract> = solc_to_contracts!(contracts);
+    for c in contracts.iter_mut() {
+        c.source_path = Some(file.as_ref().to_string_lossy().into_owned());
+        c.compiler_version = compiler_version.clone();
+    }
+    Ok(contracts)
 }
 
-pub fn compile_dir(dir: impl AsRef<Path>) -> Result<Vec<Contract>> {
+fn compile_dir_with(dir: impl AsRef<Path>, compiler_version: &Option<String>) -> Result<Vec<Contract>> {
     let mut contracts = Vec::new();
     for entry in read_dir(dir)? {
         let entry = entry?;
@@ -33,30 +66,24 @@ pub fn compile_dir(dir: impl AsRef<Path>) -> Result<Vec<Contract>> {
             continue;
         }
 
-        contracts.push(compile_file(path)?);
+        contracts.push(compile_file_with(path, compiler_version)?);
     }
     Ok(contracts.into_iter().flatten().collect())
 }
 
-pub fn compile_path(path: impl AsRef<Path>) -> Result<Vec<Contract>> {
+fn compile_path_with(path: impl AsRef<Path>, compiler_version: &Option<String>) -> Result<Vec<Contract>> {
     if path.as_ref().is_file() {
-        compile_file(path)
+        compile_file_with(path, compiler_version)
     } else {
-        compile_dir(path)
+        compile_dir_with(path, compiler_version)
     }
 }
 
-pub fn compile_paths(paths: &[impl AsRef<Path>]) -> Result<Vec<Contract>> {
-    paths.iter()
-        .map(compile_path)
-        .collect::<Result<Vec<Vec<Contract>>>>()
-        .and_then(|c| Ok(c.into_iter().flatten().collect()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::super::abi::*;
+    use crate::metadata::SourceHash;
     use std::fs::File;
     use std::io::Write;
 
@@ -76,6 +103,7 @@ mod tests {
                         name: "".to_owned(),
                         r#type: "uint256".to_owned(),
                         components: None,
+                        internalType: Some("uint256".to_owned()),
                     },
                 ],
                 stateMutability: "view".to_owned(),
@@ -89,6 +117,7 @@ mod tests {
                         name: "".to_owned(),
                         r#type: "address".to_owned(),
                         components: None,
+                        internalType: Some("address".to_owned()),
                     },
                 ],
                 stateMutability: "view".to_owned(),
@@ -100,6 +129,7 @@ mod tests {
                         name: "completed".to_owned(),
                         r#type: "uint256".to_owned(),
                         components: None,
+                        internalType: Some("uint256".to_owned()),
                     },
                 ],
                 name: "setCompleted".to_owned(),
@@ -113,6 +143,7 @@ mod tests {
                         name: "new_address".to_owned(),
                         r#type: "address".to_owned(),
                         components: None,
+                        internalType: Some("address".to_owned()),
                     },
                 ],
                 name: "upgrade".to_owned(),
@@ -124,9 +155,14 @@ mod tests {
 
         assert_eq!(contract.abi, abi);
 
-        let output_bin = "608060405234801561001057600080fd5b50336000806101000a81548173ffffffffffffffffffffffffffffffffffffffff021916908373ffffffffffffffffffffffffffffffffffffffff1602179055506102b8806100606000396000f3fe608060405234801561001057600080fd5b506004361061004c5760003560e01c80630900f01014610051578063445df0ac146100955780638da5cb5b146100b3578063fdacd576146100fd575b600080fd5b6100936004803603602081101561006757600080fd5b81019080803573ffffffffffffffffffffffffffffffffffffffff16906020019092919050505061012b565b005b61009d6101f7565b6040518082815260200191505060405180910390f35b6100bb6101fd565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6101296004803603602081101561011357600080fd5b8101908080359060200190929190505050610222565b005b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff163373ffffffffffffffffffffffffffffffffffffffff1614156101f45760008190508073ffffffffffffffffffffffffffffffffffffffff1663fdacd5766001546040518263ffffffff1660e01b815260040180828152602001915050600060405180830381600087803b1580156101da57600080fd5b505af11580156101ee573d6000803e3d6000fd5b50505050505b50565b60015481565b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1681565b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff163373ffffffffffffffffffffffffffffffffffffffff16141561027f57806001819055505b5056fea2646970667358221220";
+        let output_bin = "608060405234801561001057600080fd5b50336000806101000a81548173ffffffffffffffffffffffffffffffffffffffff021916908373ffffffffffffffffffffffffffffffffffffffff1602179055506102b8806100606000396000f3fe608060405234801561001057600080fd5b506004361061004c5760003560e01c80630900f01014610051578063445df0ac146100955780638da5cb5b146100b3578063fdacd576146100fd575b600080fd5b6100936004803603602081101561006757600080fd5b81019080803573ffffffffffffffffffffffffffffffffffffffff16906020019092919050505061012b565b005b61009d6101f7565b6040518082815260200191505060405180910390f35b6100bb6101fd565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6101296004803603602081101561011357600080fd5b8101908080359060200190929190505050610222565b005b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff163373ffffffffffffffffffffffffffffffffffffffff1614156101f45760008190508073ffffffffffffffffffffffffffffffffffffffff1663fdacd5766001546040518263ffffffff1660e01b815260040180828152602001915050600060405180830381600087803b1580156101da57600080fd5b505af11580156101ee573d6000803e3d6000fd5b50505050505b50565b60015481565b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1681565b6000809054906101000a900473ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff163373ffffffffffffffffffffffffffffffffffffffff16141561027f57806001819055505b5056fe";
+
+        assert_eq!(contract.bytecode_without_metadata(), output_bin);
+        assert!(contract.runtime_bytecode_without_metadata().ends_with("b5056fe"));
 
-        assert!(contract.bin.starts_with(output_bin));
+        let metadata = contract.metadata().expect("No metadata in the bytecode");
+        assert!(matches!(metadata.hash, Some(SourceHash::Ipfs(_))));
+        assert!(metadata.solc.unwrap().starts_with("0.6."));
     }
 
     #[test]
@@ -1,11 +1,22 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use tortilla::artifact::Format;
+use tortilla::contract::Address;
 
 pub struct Config {
     pub inputs: Vec<PathBuf>,
     pub watch: bool,
     pub output: String,
     pub pretty_print: bool,
+    pub format: Format,
     pub gas: bool,
+    pub sizes: bool,
+    /// Fails the build when a contract exceeds the size limits
+    pub fail_on_size: bool,
+    /// Pairs of (proxy, implementation) contract names to check for selector clashes
+    pub proxies: Vec<(String, String)>,
+    /// Addresses of the libraries to link, keyed by name
+    pub libraries: HashMap<String, Address>,
 }
 
 impl Config {
@@ -15,7 +26,12 @@ impl Config {
             watch: false,
             output: String::new(),
             pretty_print: false,
+            format: Format::Native,
             gas: false,
+            sizes: false,
+            fail_on_size: false,
+            proxies: Vec::new(),
+            libraries: HashMap::new(),
         }
     }
 
@@ -34,8 +50,33 @@ impl Config {
         self
     }
 
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
     pub fn gas(mut self, gas: bool) -> Self {
         self.gas = gas;
         self
     }
+
+    pub fn sizes(mut self, sizes: bool) -> Self {
+        self.sizes = sizes;
+        self
+    }
+
+    pub fn fail_on_size(mut self, fail_on_size: bool) -> Self {
+        self.fail_on_size = fail_on_size;
+        self
+    }
+
+    pub fn proxies(mut self, proxies: Vec<(String, String)>) -> Self {
+        self.proxies = proxies;
+        self
+    }
+
+    pub fn libraries(mut self, libraries: HashMap<String, Address>) -> Self {
+        self.libraries = libraries;
+        self
+    }
 }
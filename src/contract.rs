@@ -1,42 +1,312 @@
-use crate::abi::{Abi, Function};
+use crate::abi::{self, Abi, Function, Event, Log, DecodedEvent};
+use crate::artifact::{self, Format};
+use crate::linker::{self, LinkError, LinkReference};
+use crate::metadata::{self, Metadata};
 use crate::solc::SolcContract;
 use serde::{Serialize, Deserialize};
 use std::fmt;
-use std::fs::{File, DirBuilder};
+use std::fs::{self, DirBuilder, OpenOptions};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
-use ethereum_types::H160;
+use std::collections::{BTreeMap, HashMap};
+use ethereum_types::{H160, H256};
+use rustc_hex::{FromHex, ToHex};
+use tiny_keccak::keccak256;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Contract {
     pub name: String,
     pub abi: Vec<Abi>,
     pub bin: String,
+    /// Code of the deployed contract, without the constructor
+    #[serde(default)]
+    pub bin_runtime: String,
     pub gas_estimates: Option<GasEstimates>,
     pub networks: HashMap<String, Network>,
+    /// Libraries to link before deploying, offsets are those in `bin`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link_references: Vec<LinkReference>,
+    /// Metadata JSON of solc, its hash is the one appended to the bytecode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_metadata: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler_version: Option<String>,
+    /// Source maps of `bin` and `bin_runtime`, as given by solc
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_map_runtime: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Deployment of the contract on a network
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Network {
     address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transaction_hash: Option<H256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deployer: Option<Address>,
+    /// Unix time of the block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<u64>,
+    /// `0x` prefixed ABI encoding of the arguments of the constructor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    constructor_args: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    libraries: BTreeMap<String, Address>,
+    /// keccak256 of the runtime code, as returned by `EXTCODEHASH`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bytecode_hash: Option<H256>,
 }
 
 impl Network {
     pub fn new(address: Address) -> Self {
         Self {
             address,
+            transaction_hash: None,
+            block_number: None,
+            deployer: None,
+            timestamp: None,
+            constructor_args: None,
+            libraries: BTreeMap::new(),
+            bytecode_hash: None,
         }
     }
+
+    /// Sets the hash of the transaction that deployed the contract
+    pub fn with_transaction_hash(mut self, transaction_hash: H256) -> Self {
+        self.transaction_hash = Some(transaction_hash);
+        self
+    }
+
+    pub fn with_block_number(mut self, block_number: u64) -> Self {
+        self.block_number = Some(block_number);
+        self
+    }
+
+    pub fn with_deployer(mut self, deployer: Address) -> Self {
+        self.deployer = Some(deployer);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the ABI encoded arguments of the constructor
+    pub fn with_constructor_args(mut self, args: &[u8]) -> Self {
+        self.constructor_args = Some(format!("0x{}", args.to_hex::<String>()));
+        self
+    }
+
+    /// Adds a library linked into the deployed bytecode
+    pub fn with_library(mut self, name: &str, address: Address) -> Self {
+        self.libraries.insert(name.to_owned(), address);
+        self
+    }
+
+    pub fn with_bytecode_hash(mut self, bytecode_hash: H256) -> Self {
+        self.bytecode_hash = Some(bytecode_hash);
+        self
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn transaction_hash(&self) -> Option<H256> {
+        self.transaction_hash
+    }
+
+    pub fn block_number(&self) -> Option<u64> {
+        self.block_number
+    }
+
+    pub fn deployer(&self) -> Option<Address> {
+        self.deployer
+    }
+
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    pub fn constructor_args(&self) -> Option<Vec<u8>> {
+        self.constructor_args.as_ref().and_then(|args| args.trim_start_matches("0x").from_hex().ok())
+    }
+
+    pub fn libraries(&self) -> &BTreeMap<String, Address> {
+        &self.libraries
+    }
+
+    pub fn bytecode_hash(&self) -> Option<H256> {
+        self.bytecode_hash
+    }
 }
 
 pub type Address = H160;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Limit of the runtime bytecode, from EIP-170
+pub const MAX_RUNTIME_SIZE: usize = 24_576;
+/// Limit of the creation code, from EIP-3860
+pub const MAX_INITCODE_SIZE: usize = 49_152;
+
+/// Sizes of the bytecode in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeSize {
+    pub creation: usize,
+    pub runtime: usize,
+}
+
+impl CodeSize {
+    /// Bytes left under the EIP-170 limit, negative when over it
+    pub fn runtime_margin(&self) -> isize {
+        MAX_RUNTIME_SIZE as isize - self.runtime as isize
+    }
+
+    /// Bytes left under the EIP-3860 limit, negative when over it
+    pub fn initcode_margin(&self) -> isize {
+        MAX_INITCODE_SIZE as isize - self.creation as isize
+    }
+
+    pub fn exceeds_limits(&self) -> bool {
+        self.runtime_margin() < 0 || self.initcode_margin() < 0
+    }
+
+    /// Whether less than a tenth of any of the limits is left
+    pub fn is_close_to_limits(&self) -> bool {
+        self.runtime_margin() < (MAX_RUNTIME_SIZE / 10) as isize
+            || self.initcode_margin() < (MAX_INITCODE_SIZE / 10) as isize
+    }
+}
+
+/// Functions sharing a selector, as `Contract.signature`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorClash {
+    pub selector: [u8; 4],
+    pub functions: Vec<String>,
+}
+
+impl fmt::Display for SelectorClash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "selector 0x{} is shared by {}", self.selector.to_hex::<String>(), self.functions.join(", "))
+    }
+}
+
+/// Functions are keyed by their signature, e.g. `transfer(address,uint256)`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GasEstimates {
-    pub construction: String,
-    pub external: HashMap<String, String>,
-    pub internal: HashMap<String, String>,
+    pub construction: ConstructionGas,
+    pub external: HashMap<String, Gas>,
+    pub internal: HashMap<String, Gas>,
+}
+
+/// Gas estimated by solc, infinite when it depends on the inputs or the state,
+/// e.g. loops and calls to other contracts. Serialized as a number or as
+/// `"infinite"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Gas {
+    Finite(u64),
+    Infinite,
+}
+
+/// Gas of the constructor and of storing the runtime code
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct ConstructionGas {
+    pub execution: Gas,
+    pub code_deposit: Gas,
+}
+
+impl fmt::Display for Gas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gas::Finite(gas) => write!(f, "{}", gas),
+            Gas::Infinite => write!(f, "infinite"),
+        }
+    }
+}
+
+impl std::str::FromStr for Gas {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "infinite" => Ok(Gas::Infinite),
+            gas => gas.parse().map(Gas::Finite).map_err(|_| format!("Invalid gas: {}", gas)),
+        }
+    }
+}
+
+impl std::ops::Add for Gas {
+    type Output = Gas;
+
+    fn add(self, other: Gas) -> Gas {
+        match (self, other) {
+            (Gas::Finite(a), Gas::Finite(b)) => a.checked_add(b).map_or(Gas::Infinite, Gas::Finite),
+            _ => Gas::Infinite,
+        }
+    }
+}
+
+impl Serialize for Gas {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Gas::Finite(gas) => serializer.serialize_u64(*gas),
+            Gas::Infinite => serializer.serialize_str("infinite"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Gas {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Older artifacts have the estimates as strings
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Number(gas) => gas.as_u64()
+                .map(Gas::Finite)
+                .ok_or_else(|| serde::de::Error::custom(format!("Invalid gas: {}", gas))),
+            serde_json::Value::String(gas) => gas.parse().map_err(serde::de::Error::custom),
+            gas => Err(serde::de::Error::custom(format!("Invalid gas: {}", gas))),
+        }
+    }
+}
+
+impl ConstructionGas {
+    pub fn total(&self) -> Gas {
+        self.execution + self.code_deposit
+    }
+}
+
+impl fmt::Display for ConstructionGas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} + {} = {}", self.execution, self.code_deposit, self.total())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConstructionGas {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Split {
+            execution: Gas,
+            code_deposit: Gas,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Construction {
+            Split(Split),
+            // Older artifacts only have the total, kept as the execution
+            Total(Gas),
+        }
+
+        Ok(match Construction::deserialize(deserializer)? {
+            Construction::Split(Split { execution, code_deposit }) => ConstructionGas { execution, code_deposit },
+            Construction::Total(total) => ConstructionGas { execution: total, code_deposit: Gas::Finite(0) },
+        })
+    }
 }
 
 impl Contract {
@@ -45,8 +315,15 @@ impl Contract {
             name,
             abi,
             bin,
+            bin_runtime: String::new(),
             networks: HashMap::new(),
             gas_estimates: None,
+            link_references: Vec::new(),
+            raw_metadata: None,
+            source_path: None,
+            compiler_version: None,
+            source_map: None,
+            source_map_runtime: None,
         }
     }
 
@@ -54,44 +331,130 @@ impl Contract {
         serde_json::to_string_pretty(self).unwrap()
     }
 
+    /// The contract as an artifact of the given format
+    pub fn to_json(&self, format: Format, pretty_print: bool) -> String {
+        artifact::to_json(self, format, pretty_print).unwrap()
+    }
+
     pub fn write_to_dir(&self, dir: impl AsRef<Path>, pretty_print: bool) -> io::Result<PathBuf> {
+        self.write_to_dir_as(dir, Format::Native, pretty_print)
+    }
+
+    /// Writes `<name>.json` as an artifact of the given format, keeping the
+    /// networks of the artifact already there. The file is replaced at once,
+    /// readers never see it half written, and keeps its permissions
+    pub fn write_to_dir_as(&self, dir: impl AsRef<Path>, format: Format, pretty_print: bool) -> io::Result<PathBuf> {
         let dir = dir.as_ref();
         if !dir.exists() {
             DirBuilder::new()
                 .create(dir)?;
         }
 
-        let mut output_file = PathBuf::from(dir);
-        output_file.push(&self.name);
-        output_file.set_extension("json");
-
-        let mut file = File::create(&output_file)?;
-        if pretty_print {
-            write!(file, "{}", self.pretty_print())?;
+        let output_file = Self::artifact_path(dir, &self.name);
+        let previous = Self::read_from_dir(dir, &self.name)
+            .map(|c| c.networks)
+            .unwrap_or_default();
+        if previous.keys().all(|id| self.networks.contains_key(id)) {
+            self.write_to_file(&output_file, format, pretty_print)?;
         } else {
-            write!(file, "{}", self)?;
+            let mut contract = self.clone();
+            for (id, network) in previous.into_iter() {
+                contract.networks.entry(id).or_insert(network);
+            }
+            contract.write_to_file(&output_file, format, pretty_print)?;
         }
-
         Ok(output_file)
     }
 
-    pub fn write_to_dir_pretty_print(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
-        let dir = dir.as_ref();
-        if !dir.exists() {
-            DirBuilder::new()
-                .create(dir)?;
+    /// Replaces the artifact at `path` with the contract in the given format,
+    /// at once as `write_to_dir_as` does. The networks of the formats without
+    /// any are written to a file of their own, see `artifact::networks_path`
+    pub fn write_to_file(&self, path: impl AsRef<Path>, format: Format, pretty_print: bool) -> io::Result<()> {
+        let path = path.as_ref();
+        write_atomically(path, &self.to_json(format, pretty_print))?;
+        if !format.has_networks() && !self.networks.is_empty() {
+            let networks = self.networks.iter().collect::<BTreeMap<&String, &Network>>();
+            write_atomically(&artifact::networks_path(path), &serde_json::to_string_pretty(&networks)?)?;
         }
+        Ok(())
+    }
 
-        let mut output_file = PathBuf::from(dir);
-        output_file.push(&self.name);
-        output_file.set_extension("json");
+    /// Replaces the artifact at `path` keeping its format, pretty printed if
+    /// it was, e.g. to record a deployment
+    pub fn update_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let (format, pretty_print) = match fs::read_to_string(path) {
+            Ok(json) => (
+                serde_json::from_str(&json).map(|value| Format::detect(&value)).unwrap_or_default(),
+                json.trim().contains('\n'),
+            ),
+            Err(_) => (Format::Native, false),
+        };
+        self.write_to_file(path, format, pretty_print)
+    }
 
-        let mut file = File::create(&output_file)?;
-        write!(file, "{}", self.pretty_print())?;
+    /// Reads `<name>.json` from the directory, see `read_from_file`
+    pub fn read_from_dir(dir: impl AsRef<Path>, name: &str) -> io::Result<Self> {
+        Self::read_from_file(Self::artifact_path(dir.as_ref(), name))
+    }
 
-        Ok(output_file)
+    /// Reads an artifact in any of the formats, with the deployments kept
+    /// next to it for the formats without networks. The contract is named
+    /// after the file when the artifact doesn't tell it
+    pub fn read_from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)?;
+        let (format, mut contract) = artifact::from_json_unnamed(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if contract.name.is_empty() {
+            contract.name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        }
+
+        if !format.has_networks() {
+            match fs::read_to_string(artifact::networks_path(path)) {
+                Ok(json) => contract.networks = serde_json::from_str(&json)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(contract)
+    }
+
+    fn artifact_path(dir: &Path, name: &str) -> PathBuf {
+        let mut path = PathBuf::from(dir);
+        path.push(name);
+        path.set_extension("json");
+        path
     }
 
+    /// Networks of the previous build of the contract where the deployed
+    /// runtime code differs from the one of this build
+    pub fn outdated_networks<'a>(&self, previous: &'a Contract) -> Vec<&'a str> {
+        let mut outdated = previous.list_networks().into_iter()
+            .filter(|(_, network)| {
+                let libraries = network.libraries().iter()
+                    .map(|(name, address)| (name.clone(), *address))
+                    .collect::<HashMap<String, Address>>();
+                match (network.bytecode_hash(), self.link_runtime(&libraries)) {
+                    (Some(hash), Ok(runtime)) => match runtime.from_hex::<Vec<u8>>() {
+                        Ok(code) => H256::from(keccak256(&code)) != hash,
+                        Err(_) => previous.bin_runtime != self.bin_runtime,
+                    },
+                    _ => previous.bin_runtime != self.bin_runtime,
+                }
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<&str>>();
+        outdated.sort_unstable();
+        outdated
+    }
+
+    pub fn write_to_dir_pretty_print(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        self.write_to_dir_as(dir, Format::Native, true)
+    }
+
+    /// Functions keyed by bare name, overloads overwrite each other, see `get_functions`
     pub fn get_methods(&self) -> HashMap<String, Function> {
         let mut methods = HashMap::new();
         for el in self.abi.iter() {
@@ -102,6 +465,178 @@ impl Contract {
         methods
     }
 
+    /// Functions grouped by name, overloads in order of declaration
+    pub fn get_functions(&self) -> HashMap<String, Vec<&Function>> {
+        let mut functions = HashMap::new();
+        for f in self.functions() {
+            functions.entry(f.name.clone()).or_insert_with(Vec::new).push(f);
+        }
+        functions
+    }
+
+    /// Looks up a function by its full signature, e.g. `safeTransferFrom(address,address,uint256)`
+    pub fn get_function(&self, signature: &str) -> Option<&Function> {
+        let signature = normalize_signature(signature);
+        self.functions().find(|f| f.signature().ok().as_ref() == Some(&signature))
+    }
+
+    /// Looks up a function by its signature or by its name, overloads being
+    /// told apart by their number of arguments
+    pub fn find_function(&self, name: &str, args: usize) -> ethabi::Result<&Function> {
+        if name.contains('(') {
+            return self.get_function(name)
+                .ok_or_else(|| ethabi::Error::Other(format!("{} has no function {}", self.name, name)));
+        }
+
+        let functions = self.functions().filter(|f| f.name == name).collect::<Vec<&Function>>();
+        let candidates = functions.iter().filter(|f| f.inputs.len() == args).collect::<Vec<_>>();
+        match (functions.len(), candidates.as_slice()) {
+            (0, _) => Err(ethabi::Error::Other(format!("{} has no function {}", self.name, name))),
+            (_, [function]) => Ok(function),
+            (1, []) => Err(ethabi::Error::Other(format!(
+                "{} takes {} argument(s), got {}", name, functions[0].inputs.len(), args
+            ))),
+            _ => Err(ethabi::Error::Other(format!(
+                "{} is overloaded, use one of {}",
+                name,
+                functions.iter().filter_map(|f| f.signature().ok()).collect::<Vec<String>>().join(", ")
+            ))),
+        }
+    }
+
+    pub fn get_function_by_selector(&self, selector: [u8; 4]) -> Option<&Function> {
+        self.functions().find(|f| f.selector().ok() == Some(selector))
+    }
+
+    /// Events grouped by name, overloads in order of declaration
+    pub fn get_events(&self) -> HashMap<String, Vec<&Event>> {
+        let mut events = HashMap::new();
+        for e in self.events() {
+            events.entry(e.name.clone()).or_insert_with(Vec::new).push(e);
+        }
+        events
+    }
+
+    /// Looks up an event by its full signature, e.g. `Transfer(address,address,uint256)`
+    pub fn get_event(&self, signature: &str) -> Option<&Event> {
+        let signature = normalize_signature(signature);
+        self.events().find(|e| e.signature().ok().as_ref() == Some(&signature))
+    }
+
+    pub fn get_event_by_topic(&self, topic: H256) -> Option<&Event> {
+        self.events().find(|e| !e.anonymous && e.topic().ok() == Some(topic))
+    }
+
+    /// Custom errors grouped by name, overloads in order of declaration
+    pub fn get_errors(&self) -> HashMap<String, Vec<&abi::Error>> {
+        let mut errors = HashMap::new();
+        for e in self.errors() {
+            errors.entry(e.name.clone()).or_insert_with(Vec::new).push(e);
+        }
+        errors
+    }
+
+    /// Looks up a custom error by its full signature, e.g. `Unauthorized(address)`
+    pub fn get_error(&self, signature: &str) -> Option<&abi::Error> {
+        let signature = normalize_signature(signature);
+        self.errors().find(|e| e.signature().ok().as_ref() == Some(&signature))
+    }
+
+    pub fn get_error_by_selector(&self, selector: [u8; 4]) -> Option<&abi::Error> {
+        self.errors().find(|e| e.selector().ok() == Some(selector))
+    }
+
+    /// Signature and selector of every function, in order of declaration
+    pub fn selectors(&self) -> Vec<(String, [u8; 4])> {
+        self.functions()
+            .filter_map(|f| Some((f.signature().ok()?, f.selector().ok()?)))
+            .collect()
+    }
+
+    /// Functions of the contract whose selectors collide
+    pub fn selector_clashes(&self) -> Vec<SelectorClash> {
+        find_clashes(&[self])
+    }
+
+    /// Functions of the implementation that can't be reached through the
+    /// proxy because the proxy has a function with the same selector
+    pub fn proxy_clashes(&self, implementation: &Contract) -> Vec<SelectorClash> {
+        group_selectors(&[self, implementation])
+            .into_iter()
+            .filter(|(_, functions)| functions.iter().any(|(i, _)| *i == 0) && functions.iter().any(|(i, _)| *i == 1))
+            .map(|(selector, functions)| SelectorClash {
+                selector,
+                functions: functions.into_iter().map(|(_, f)| f).collect(),
+            })
+            .collect()
+    }
+
+    fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.abi.iter().filter_map(|a| match a {
+            Abi::Function(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    fn events(&self) -> impl Iterator<Item = &Event> {
+        self.abi.iter().filter_map(|a| match a {
+            Abi::Event(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    fn errors(&self) -> impl Iterator<Item = &abi::Error> {
+        self.abi.iter().filter_map(|a| match a {
+            Abi::Error(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Finds the event that emitted the log by its topic 0, anonymous events
+    /// are tried in order of declaration when no other event matches
+    pub fn decode_log(&self, log: &Log) -> ethabi::Result<DecodedEvent> {
+        if let Some(event) = log.topics.first().and_then(|&topic| self.get_event_by_topic(topic)) {
+            return event.decode_log(log);
+        }
+
+        self.events()
+            .filter(|e| e.anonymous)
+            .find_map(|e| e.decode_log_strict(log).ok())
+            .ok_or_else(|| ethabi::Error::Other(format!("No event of {} matches the log", self.name)))
+    }
+
+    /// Creation code with the addresses of the libraries, keyed by their
+    /// names, fully qualified or not
+    pub fn link(&self, libraries: &HashMap<String, Address>) -> Result<String, LinkError> {
+        link(&self.bin, &self.link_references, libraries)
+    }
+
+    /// Runtime code with the addresses of the libraries, its placeholders are
+    /// the ones of the creation code embedding it
+    pub fn link_runtime(&self, libraries: &HashMap<String, Address>) -> Result<String, LinkError> {
+        link(&self.bin_runtime, &self.link_references, libraries)
+    }
+
+    /// Metadata at the end of the runtime bytecode, or of the creation code
+    /// when the runtime one is unknown
+    pub fn metadata(&self) -> Option<Metadata> {
+        if self.bin_runtime.is_empty() {
+            metadata::from_bytecode(&self.bin)
+        } else {
+            metadata::from_bytecode(&self.bin_runtime)
+        }
+    }
+
+    /// Creation code without the trailing metadata, which changes with the
+    /// comments and paths of the sources
+    pub fn bytecode_without_metadata(&self) -> &str {
+        metadata::split(&self.bin).map_or(&self.bin, |(code, _)| code)
+    }
+
+    pub fn runtime_bytecode_without_metadata(&self) -> &str {
+        metadata::split(&self.bin_runtime).map_or(&self.bin_runtime, |(code, _)| code)
+    }
+
     pub fn add_network(&mut self, net_version: &str, address: Address) {
         self.networks.insert(net_version.to_owned(), Network::new(address));
     }
@@ -110,16 +645,63 @@ impl Contract {
         self.networks.get(net_version).map(|n| n.address)
     }
 
+    pub fn get_network(&self, net_version: &str) -> Option<&Network> {
+        self.networks.get(net_version)
+    }
+
+    /// Adds or replaces the deployment on the network, returns the previous one
+    pub fn set_network(&mut self, net_version: &str, network: Network) -> Option<Network> {
+        self.networks.insert(net_version.to_owned(), network)
+    }
+
+    pub fn remove_network(&mut self, net_version: &str) -> Option<Network> {
+        self.networks.remove(net_version)
+    }
+
+    /// Deployments sorted by network id
+    pub fn list_networks(&self) -> Vec<(&str, &Network)> {
+        let mut networks = self.networks.iter()
+            .map(|(id, network)| (id.as_str(), network))
+            .collect::<Vec<(&str, &Network)>>();
+        networks.sort_by_key(|(id, _)| *id);
+        networks
+    }
+
     pub fn from_solc_contract(c: SolcContract) -> Self {
         Self {
             name: c.name,
             abi: c.abi,
+            link_references: linker::find_references(&c.bin, &c.libraries),
             bin: c.bin,
-            gas_estimates: Some(c.gas_estimates),
+            bin_runtime: c.bin_runtime,
+            gas_estimates: c.gas_estimates,
             networks: HashMap::new(),
+            raw_metadata: c.metadata,
+            source_path: None,
+            compiler_version: None,
+            source_map: c.source_map,
+            source_map_runtime: c.source_map_runtime,
+        }
+    }
+
+    /// Sizes of `bin` and `bin_runtime`, placeholders of libraries count as
+    /// the addresses replacing them
+    pub fn code_size(&self) -> CodeSize {
+        CodeSize {
+            creation: self.bin.len() / 2,
+            runtime: self.bin_runtime.len() / 2,
         }
     }
 
+    pub fn code_size_to_string(&self) -> String {
+        let size = self.code_size();
+        format!(
+            "creation: {} bytes ({} left of {})\n runtime: {} bytes ({} left of {})",
+            size.creation, size.initcode_margin(), MAX_INITCODE_SIZE,
+            size.runtime, size.runtime_margin(), MAX_RUNTIME_SIZE
+        )
+    }
+
     pub fn gas_estimates_to_string(&self) -> String {
         let mut output = Vec::new();
 
@@ -160,21 +742,89 @@ impl Contract {
         output.join("\n")
     }
 
+    /// Reads an artifact of any of the supported formats
     pub fn from_json(json: &str) -> Result<Self, serde_json::error::Error> {
-        serde_json::from_str(json)
+        artifact::from_json(json)
     }
 }
 
+fn find_clashes(contracts: &[&Contract]) -> Vec<SelectorClash> {
+    group_selectors(contracts).into_iter()
+        .filter(|(_, functions)| functions.len() > 1)
+        .map(|(selector, functions)| SelectorClash {
+            selector,
+            functions: functions.into_iter().map(|(_, f)| f).collect(),
+        })
+        .collect()
+}
+
+/// `Contract.signature` of the functions sharing a selector, along with the
+/// position of their contract so contracts sharing a name stay apart
+type SelectorGroup = ([u8; 4], Vec<(usize, String)>);
+
+fn group_selectors(contracts: &[&Contract]) -> Vec<SelectorGroup> {
+    let mut selectors: Vec<SelectorGroup> = Vec::new();
+    for (i, contract) in contracts.iter().enumerate() {
+        for (signature, selector) in contract.selectors() {
+            let function = (i, format!("{}.{}", contract.name, signature));
+            match selectors.iter_mut().find(|(s, _)| *s == selector) {
+                Some((_, functions)) => functions.push(function),
+                None => selectors.push((selector, vec![function])),
+            }
+        }
+    }
+    selectors
+}
+
+fn link(bin: &str, references: &[LinkReference], libraries: &HashMap<String, Address>) -> Result<String, LinkError> {
+    let (bin, unresolved) = linker::link(bin, references, libraries);
+    if unresolved.is_empty() {
+        Ok(bin)
+    } else {
+        Err(LinkError {
+            unresolved: unresolved.into_iter().map(|r| r.name.clone()).collect(),
+        })
+    }
+}
+
+fn normalize_signature(signature: &str) -> String {
+    signature.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
 impl fmt::Display for Contract {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", serde_json::to_string(self).unwrap())
     }
 }
 
+/// Writes to a temporary file renamed over `path`. It gets the permissions
+/// of the file it replaces, a new file is first created empty for the umask
+/// to apply to it as to any other file, rather than the owner only mode of
+/// the temporary file
+fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    write!(file, "{}", content)?;
+
+    let permissions = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?
+        .metadata()?
+        .permissions();
+    file.as_file().set_permissions(permissions)?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::compiler;
+    use crate::abi::{human, DecodedValue, Token};
+    use ethereum_types::H256;
+    use std::fs::File;
     use std::io::Read;
 
     #[test]
@@ -243,4 +893,331 @@ mod tests {
 
         assert_eq!(contract, from_json);
     }
+
+    #[test]
+    fn test_decode_log() {
+        let abi = Abi::from_json_array(r#"[
+            {
+              "anonymous": false,
+              "inputs": [
+                { "indexed": false, "name": "winner", "type": "address" },
+                { "indexed": false, "name": "amount", "type": "uint256" }
+              ],
+              "name": "AuctionEnded",
+              "type": "event"
+            },
+            {
+              "anonymous": true,
+              "inputs": [
+                { "indexed": true, "name": "bidder", "type": "address" },
+                { "indexed": false, "name": "amount", "type": "uint256" }
+              ],
+              "name": "Deposit",
+              "type": "event"
+            },
+            {
+              "anonymous": true,
+              "inputs": [
+                { "indexed": false, "name": "text", "type": "string" }
+              ],
+              "name": "Note",
+              "type": "event"
+            }
+        ]"#).unwrap();
+        let contract = Contract::new("Auction".to_owned(), abi, String::new());
+
+        let word = |s: &str| format!("{:0>64}", s).parse::<H256>().unwrap();
+        let data = |words: &[&str]| words.iter()
+            .flat_map(|w| word(w).as_bytes().to_vec())
+            .collect::<Vec<u8>>();
+
+        let ended = Log {
+            topics: vec![H256::from(tiny_keccak::keccak256(b"AuctionEnded(address,uint256)"))],
+            data: data(&["1111111111111111111111111111111111111111", "64"]),
+        };
+        let decoded = contract.decode_log(&ended).unwrap();
+        assert_eq!(decoded.name, "AuctionEnded");
+        assert_eq!(decoded.get("amount"), Some(&DecodedValue::Token(Token::Uint(100.into()))));
+
+        let deposit = Log {
+            topics: vec![word("2222222222222222222222222222222222222222")],
+            data: data(&["5"]),
+        };
+        let decoded = contract.decode_log(&deposit).unwrap();
+        assert_eq!(decoded.name, "Deposit");
+        assert_eq!(decoded.get("bidder"), Some(&DecodedValue::Token(Token::Address([0x22; 20].into()))));
+
+        let note = Log {
+            topics: vec![],
+            data: data(&["20", "3", "6869210000000000000000000000000000000000000000000000000000000000"]),
+        };
+        let decoded = contract.decode_log(&note).unwrap();
+        assert_eq!(decoded.name, "Note");
+        assert_eq!(decoded.get("text"), Some(&DecodedValue::Token(Token::String("hi!".to_owned()))));
+
+        let unknown = Log {
+            topics: vec![word("1"), word("2")],
+            data: vec![],
+        };
+        assert!(contract.decode_log(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_get_overloads() {
+        let abi = Abi::from_json_array(r#"[
+            {
+              "inputs": [
+                { "name": "from", "type": "address" },
+                { "name": "to", "type": "address" },
+                { "name": "tokenId", "type": "uint256" }
+              ],
+              "name": "safeTransferFrom",
+              "outputs": [],
+              "stateMutability": "nonpayable",
+              "type": "function"
+            },
+            {
+              "inputs": [
+                { "name": "from", "type": "address" },
+                { "name": "to", "type": "address" },
+                { "name": "tokenId", "type": "uint256" },
+                { "name": "data", "type": "bytes" }
+              ],
+              "name": "safeTransferFrom",
+              "outputs": [],
+              "stateMutability": "nonpayable",
+              "type": "function"
+            },
+            {
+              "anonymous": false,
+              "inputs": [
+                { "indexed": true, "name": "from", "type": "address" },
+                { "indexed": true, "name": "to", "type": "address" },
+                { "indexed": true, "name": "tokenId", "type": "uint256" }
+              ],
+              "name": "Transfer",
+              "type": "event"
+            },
+            {
+              "inputs": [
+                { "name": "tokenId", "type": "uint256" }
+              ],
+              "name": "NonexistentToken",
+              "type": "error"
+            }
+        ]"#).unwrap();
+        let contract = Contract::new("Token".to_owned(), abi, String::new());
+
+        let functions = contract.get_functions();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions["safeTransferFrom"].len(), 2);
+
+        let with_data = contract.get_function("safeTransferFrom(address, address, uint256, bytes)").unwrap();
+        assert_eq!(with_data.inputs.len(), 4);
+        assert_eq!(contract.get_function_by_selector([0xb8, 0x8d, 0x4f, 0xde]), Some(with_data));
+
+        let without_data = contract.get_function_by_selector([0x42, 0x84, 0x2e, 0x0e]).unwrap();
+        assert_eq!(without_data.inputs.len(), 3);
+        assert_eq!(contract.get_function("safeTransferFrom(address,address)"), None);
+        assert_eq!(contract.find_function("safeTransferFrom", 4).unwrap(), with_data);
+        assert_eq!(contract.find_function("safeTransferFrom(address,address,uint256)", 0).unwrap(), without_data);
+        assert!(contract.find_function("safeTransferFrom", 2).unwrap_err().to_string().contains("overloaded"));
+        assert!(contract.find_function("transfer", 2).is_err());
+
+        assert_eq!(contract.get_events()["Transfer"].len(), 1);
+        let transfer = contract.get_event("Transfer(address,address,uint256)").unwrap();
+        assert_eq!(contract.get_event_by_topic(transfer.topic().unwrap()), Some(transfer));
+
+        assert_eq!(contract.get_errors()["NonexistentToken"].len(), 1);
+        let error = contract.get_error("NonexistentToken(uint256)").unwrap();
+        assert_eq!(contract.get_error_by_selector(error.selector().unwrap()), Some(error));
+    }
+
+    #[test]
+    fn test_selector_clashes() {
+        let abi = human::parse_all("
+            function burn(uint256 amount)
+            function collate_propagate_storage(bytes16 data)
+            function owner() view returns (address)
+        ").unwrap();
+        let contract = Contract::new("Token".to_owned(), abi, String::new());
+
+        let clashes = contract.selector_clashes();
+        assert_eq!(clashes, vec![SelectorClash {
+            selector: [0x42, 0x96, 0x6c, 0x68],
+            functions: vec![
+                "Token.burn(uint256)".to_owned(),
+                "Token.collate_propagate_storage(bytes16)".to_owned(),
+            ],
+        }]);
+        assert_eq!(
+            clashes[0].to_string(),
+            "selector 0x42966c68 is shared by Token.burn(uint256), Token.collate_propagate_storage(bytes16)"
+        );
+    }
+
+    #[test]
+    fn test_proxy_clashes() {
+        let proxy = Contract::new("Proxy".to_owned(), human::parse_all("
+            function upgradeTo(address implementation)
+            function collate_propagate_storage(bytes16 data)
+        ").unwrap(), String::new());
+        let implementation = Contract::new("Box".to_owned(), human::parse_all("
+            function burn(uint256 amount)
+            function upgradeTo(address implementation)
+            function store(uint256 value)
+        ").unwrap(), String::new());
+
+        assert!(proxy.selector_clashes().is_empty());
+        assert!(implementation.selector_clashes().is_empty());
+
+        let clashes = proxy.proxy_clashes(&implementation);
+        assert_eq!(clashes.len(), 2);
+        assert_eq!(clashes[0].functions, vec!["Proxy.upgradeTo(address)", "Box.upgradeTo(address)"]);
+        assert_eq!(clashes[1].functions, vec!["Proxy.collate_propagate_storage(bytes16)", "Box.burn(uint256)"]);
+
+        // A proxy named like its implementation
+        let mut same_name = implementation.clone();
+        same_name.name = "Proxy".to_owned();
+        assert_eq!(proxy.proxy_clashes(&same_name).len(), 2);
+    }
+
+    #[test]
+    fn test_link() {
+        let math = linker::placeholder("contracts/Math.sol:Math");
+        let mut libraries = HashMap::new();
+        libraries.insert(math.clone(), "contracts/Math.sol:Math".to_owned());
+        let mut contract = Contract::new("Vault".to_owned(), Vec::new(), format!("6080{}00", math));
+        contract.bin_runtime = format!("60{}", math);
+        contract.link_references = linker::find_references(&contract.bin, &libraries);
+
+        let err = contract.link(&HashMap::new()).unwrap_err();
+        assert_eq!(err.unresolved, vec!["contracts/Math.sol:Math"]);
+
+        let mut addresses = HashMap::new();
+        addresses.insert("Math".to_owned(), "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap());
+        assert_eq!(contract.link(&addresses).unwrap(), "6080e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab00");
+        assert_eq!(contract.link_runtime(&addresses).unwrap(), "60e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab");
+    }
+
+    #[test]
+    fn test_code_size() {
+        let mut contract = Contract::new("Token".to_owned(), Vec::new(), "60".repeat(1000));
+        contract.bin_runtime = "60".repeat(800);
+
+        let size = contract.code_size();
+        assert_eq!(size, CodeSize { creation: 1000, runtime: 800 });
+        assert_eq!(size.runtime_margin(), 23_776);
+        assert_eq!(size.initcode_margin(), 48_152);
+        assert!(!size.exceeds_limits());
+        assert!(!size.is_close_to_limits());
+        assert_eq!(contract.code_size_to_string(), "creation: 1000 bytes (48152 left of 49152)\n runtime: 800 bytes (23776 left of 24576)");
+
+        contract.bin_runtime = "60".repeat(23_000);
+        assert!(contract.code_size().is_close_to_limits());
+        assert!(!contract.code_size().exceeds_limits());
+
+        contract.bin_runtime = "60".repeat(MAX_RUNTIME_SIZE + 1);
+        assert_eq!(contract.code_size().runtime_margin(), -1);
+        assert!(contract.code_size().exceeds_limits());
+    }
+
+    #[test]
+    fn test_gas_serde() {
+        let json = r#"{"construction":{"execution":93,"code_deposit":79000},"external":{"f(uint256)":"infinite"},"internal":{}}"#;
+        let gas: GasEstimates = serde_json::from_str(json).unwrap();
+        assert_eq!(gas.construction.total(), Gas::Finite(79093));
+        assert_eq!(gas.construction.to_string(), "93 + 79000 = 79093");
+        assert_eq!(gas.external["f(uint256)"], Gas::Infinite);
+        assert_eq!(serde_json::to_string(&gas).unwrap(), json);
+
+        // Estimates of older artifacts
+        let json = r#"{"construction":"57305","external":{"helloWorld":"1200"},"internal":{}}"#;
+        let gas: GasEstimates = serde_json::from_str(json).unwrap();
+        assert_eq!(gas.construction.total(), Gas::Finite(57305));
+        assert_eq!(gas.external["helloWorld"], Gas::Finite(1200));
+
+        assert!(serde_json::from_str::<Gas>(r#""lots""#).is_err());
+        assert_eq!(Gas::Finite(u64::MAX) + Gas::Finite(1), Gas::Infinite);
+    }
+
+    #[test]
+    fn test_networks() {
+        let address: Address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+        let network = Network::new(address)
+            .with_block_number(42)
+            .with_deployer(Address::zero())
+            .with_timestamp(1_600_000_000)
+            .with_constructor_args(&[0x12, 0x34])
+            .with_library("contracts/Math.sol:Math", address);
+        assert_eq!(network.constructor_args(), Some(vec![0x12, 0x34]));
+
+        let json = serde_json::to_value(&network).unwrap();
+        assert_eq!(json["constructor_args"], "0x1234");
+        assert_eq!(json["libraries"]["contracts/Math.sol:Math"], "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab");
+        assert!(json.get("transaction_hash").is_none());
+        assert_eq!(serde_json::from_value::<Network>(json).unwrap(), network);
+
+        let mut contract = Contract::new("Token".to_owned(), Vec::new(), String::new());
+        assert!(contract.set_network("5", network.clone()).is_none());
+        contract.add_network("1", address);
+        assert_eq!(contract.set_network("5", Network::new(address)), Some(network));
+        assert_eq!(contract.list_networks().iter().map(|(id, _)| *id).collect::<Vec<&str>>(), vec!["1", "5"]);
+        assert!(contract.remove_network("1").is_some());
+        assert!(contract.get_network("1").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let contract = Contract::new("Token".to_owned(), Vec::new(), "6080".to_owned());
+        let path = contract.write_to_dir(dir.path(), false).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let created = dir.path().join("created");
+        File::create(&created).unwrap();
+        assert_eq!(mode(&path), mode(&created));
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        contract.write_to_file(&path, Format::Truffle, true).unwrap();
+        assert_eq!(mode(&path), 0o640);
+    }
+
+    #[test]
+    fn test_write_keeps_networks() {
+        let dir = tempfile::tempdir().unwrap();
+        let address: Address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+
+        let mut deployed = Contract::new("Token".to_owned(), Vec::new(), "6080".to_owned());
+        deployed.add_network("5", address);
+        deployed.write_to_dir_as(dir.path(), Format::Truffle, false).unwrap();
+
+        let mut rebuilt = Contract::new("Token".to_owned(), Vec::new(), "6081".to_owned());
+        rebuilt.add_network("1", Address::zero());
+        let path = rebuilt.write_to_dir_as(dir.path(), Format::Truffle, false).unwrap();
+
+        let written = Contract::from_json(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written.bin, "6081");
+        assert_eq!(written.get_address("5"), Some(address));
+        assert_eq!(written.get_address("1"), Some(Address::zero()));
+    }
+
+    #[test]
+    fn test_outdated_networks() {
+        let address: Address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+        let mut previous = Contract::new("Token".to_owned(), Vec::new(), "6080".to_owned());
+        previous.bin_runtime = "6001".to_owned();
+        previous.add_network("1", address);
+        // Deployed before the previous build, the hash tells the code apart
+        previous.set_network("5", Network::new(address).with_bytecode_hash(H256::from(keccak256(&[0x60, 0x02]))));
+
+        let mut contract = Contract::new("Token".to_owned(), Vec::new(), "6080".to_owned());
+        contract.bin_runtime = "6001".to_owned();
+        assert_eq!(contract.outdated_networks(&previous), vec!["5"]);
+
+        contract.bin_runtime = "6002".to_owned();
+        assert_eq!(contract.outdated_networks(&previous), vec!["1"]);
+    }
 }
@@ -0,0 +1,356 @@
+//! Deployment of compiled contracts through the JSON-RPC of a node, either
+//! from an account unlocked in the node or from a local keystore.
+
+use crate::abi::{Abi, Token};
+use crate::contract::{Address, Contract, Network};
+use crate::rpc::{self, Client, Receipt, TransactionRequest};
+use crate::wallet::{self, Wallet};
+use ethereum_types::{H256, U256};
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+use tiny_keccak::keccak256;
+
+pub struct Deployer<'a> {
+    client: &'a Client,
+    from: Option<Address>,
+    wallet: Option<Wallet>,
+    gas: Option<U256>,
+    gas_price: Option<U256>,
+    value: Option<U256>,
+    libraries: HashMap<String, Address>,
+    timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deployment {
+    /// Id of the network in `Contract::networks`
+    pub net_version: String,
+    pub network: Network,
+    pub receipt: Receipt,
+}
+
+impl<'a> Deployer<'a> {
+    /// Sends from the first account of the node unless told otherwise
+    pub fn new(client: &'a Client) -> Self {
+        Self {
+            client,
+            from: None,
+            wallet: None,
+            gas: None,
+            gas_price: None,
+            value: None,
+            libraries: HashMap::new(),
+            timeout: Duration::from_secs(120),
+        }
+    }
+
+    /// Sends from an account unlocked in the node
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Signs the transactions locally, for nodes without unlocked accounts
+    pub fn wallet(mut self, wallet: Wallet) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    /// Gas limit, estimated by the node when not given
+    pub fn gas(mut self, gas: U256) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// Wei sent to a payable constructor or function
+    pub fn value(mut self, value: U256) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Libraries to link before deploying
+    pub fn libraries(mut self, libraries: HashMap<String, Address>) -> Self {
+        self.libraries = libraries;
+        self
+    }
+
+    /// How long to wait for the transaction to be mined
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends the creation transaction and waits for it to be mined, the
+    /// deployment is recorded in the networks of the contract
+    pub fn deploy(&self, contract: &mut Contract, args: &[Token]) -> io::Result<Deployment> {
+        let bin = contract.link(&self.libraries)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", contract.name, e)))?;
+        if bin.is_empty() {
+            return Err(invalid_input(format!("{} has no bytecode, is it abstract or an interface?", contract.name)));
+        }
+        let code = rpc::decode_hex(&bin)?;
+
+        let data = match contract.abi.iter().find_map(|abi| match abi {
+            Abi::Constructor(constructor) => Some(constructor),
+            _ => None,
+        }) {
+            Some(constructor) => constructor.encode(&code, args)
+                .map_err(|e| invalid_input(format!("Invalid arguments of the constructor of {}: {}", contract.name, e)))?,
+            None if args.is_empty() => code.clone(),
+            None => return Err(invalid_input(format!("{} has no constructor taking arguments", contract.name))),
+        };
+
+        let from = self.sender()?;
+        let mut transaction = TransactionRequest {
+            from: Some(from),
+            to: None,
+            gas: self.gas,
+            gas_price: self.gas_price,
+            value: self.value,
+            data,
+            nonce: None,
+        };
+
+        let hash = self.send(&mut transaction)?;
+        let receipt = self.client.wait_for_receipt(hash, self.timeout)?;
+        let address = match receipt.contract_address {
+            Some(address) if receipt.succeeded() => address,
+            _ => return Err(io::Error::other(format!("The deployment of {} reverted in 0x{:x}", contract.name, hash))),
+        };
+
+        let mut network = Network::new(address)
+            .with_transaction_hash(hash)
+            .with_block_number(receipt.block_number.as_u64())
+            .with_deployer(from)
+            .with_timestamp(self.client.block_timestamp(receipt.block_number)?)
+            .with_bytecode_hash(H256::from(keccak256(&self.client.code(address)?)));
+        if transaction.data.len() > code.len() {
+            network = network.with_constructor_args(&transaction.data[code.len()..]);
+        }
+        for reference in contract.link_references.iter() {
+            if let Some((name, address)) = reference.find_library(&self.libraries) {
+                network = network.with_library(name, *address);
+            }
+        }
+
+        let net_version = self.client.net_version()?;
+        contract.set_network(&net_version, network.clone());
+        Ok(Deployment { net_version, network, receipt })
+    }
+
+    /// Sends a transaction to a deployed contract, e.g. the calldata of one of
+    /// its functions, and waits for it to be mined
+    pub fn transact(&self, to: Address, data: Vec<u8>) -> io::Result<Receipt> {
+        let mut transaction = TransactionRequest {
+            from: Some(self.sender()?),
+            to: Some(to),
+            gas: self.gas,
+            gas_price: self.gas_price,
+            value: self.value,
+            data,
+            nonce: None,
+        };
+
+        let hash = self.send(&mut transaction)?;
+        let receipt = self.client.wait_for_receipt(hash, self.timeout)?;
+        if !receipt.succeeded() {
+            return Err(io::Error::other(format!("The transaction 0x{:x} reverted", hash)));
+        }
+        Ok(receipt)
+    }
+
+    /// Address of the wallet, of `from` or of the first account of the node
+    pub fn sender(&self) -> io::Result<Address> {
+        match (&self.wallet, self.from) {
+            (Some(wallet), _) => Ok(wallet.address()),
+            (None, Some(from)) => Ok(from),
+            (None, None) => self.client.accounts()?
+                .first()
+                .copied()
+                .ok_or_else(|| invalid_input("The node has no unlocked accounts, use a keystore".to_owned())),
+        }
+    }
+
+    /// Sends the transaction through the node, or signed by the wallet
+    pub(crate) fn send(&self, transaction: &mut TransactionRequest) -> io::Result<H256> {
+        let wallet = match &self.wallet {
+            Some(wallet) => wallet,
+            None => return self.client.send_transaction(transaction),
+        };
+
+        if transaction.nonce.is_none() {
+            transaction.nonce = Some(self.client.transaction_count(wallet.address())?);
+        }
+        if transaction.gas_price.is_none() {
+            transaction.gas_price = Some(self.client.gas_price()?);
+        }
+        if transaction.gas.is_none() {
+            transaction.gas = Some(self.client.estimate_gas(transaction)?);
+        }
+        let raw = wallet.sign_transaction(transaction, self.client.chain_id()?)?;
+        let hash = self.client.send_raw_transaction(&raw)?;
+        if hash != wallet::transaction_hash(&raw) {
+            return Err(io::Error::other(format!("The node returned 0x{:x} for a different transaction", hash)));
+        }
+        Ok(hash)
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+    use crate::artifact::Format;
+    use crate::rpc::mock::MockNode;
+    use rustc_hex::FromHex;
+    use serde_json::json;
+
+    const ADDRESS: &str = "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab";
+    const HASH: &str = "0xf2b9e5c7d0a7e3a6d2e2b6f0a5e4a0f7c1b2d3e4f5a6b7c8d9e0f1a2b3c4d5e6";
+
+    fn node(status: &'static str) -> MockNode {
+        MockNode::start(move |method, params| Ok(match method {
+            "net_version" => json!("5777"),
+            "eth_chainId" => json!("0x539"),
+            "eth_accounts" => json!(["0x0000000000000000000000000000000000000001"]),
+            "eth_getTransactionCount" => json!("0x3"),
+            "eth_gasPrice" => json!("0x4a817c800"),
+            "eth_estimateGas" => json!("0x30d40"),
+            "eth_sendTransaction" => json!(HASH),
+            "eth_sendRawTransaction" => {
+                let raw: Vec<u8> = params[0].as_str().unwrap()[2..].from_hex().unwrap();
+                json!(wallet::transaction_hash(&raw))
+            },
+            "eth_getTransactionReceipt" => json!({
+                "transactionHash": params[0],
+                "blockNumber": "0x2a",
+                "contractAddress": ADDRESS,
+                "gasUsed": "0x1234",
+                "status": status,
+                "logs": []
+            }),
+            "eth_getBlockByNumber" => json!({ "number": "0x2a", "timestamp": "0x5f5e1000" }),
+            "eth_getCode" => json!("0x6080"),
+            _ => return Err(json!({ "code": -32601, "message": "method not found" })),
+        }))
+    }
+
+    fn contract() -> Contract {
+        let abi = human::parse_all("constructor(uint256 supply)").unwrap();
+        Contract::new("Token".to_owned(), abi, "6080604052".to_owned())
+    }
+
+    #[test]
+    fn test_deploy_unlocked() {
+        let node = node("0x1");
+        let client = Client::new(&node.url()).unwrap();
+        let mut contract = contract();
+
+        let deployment = Deployer::new(&client).deploy(&mut contract, &[Token::Uint(U256::from(1000))]).unwrap();
+        assert_eq!(deployment.net_version, "5777");
+        assert_eq!(deployment.network.address(), ADDRESS[2..].parse().unwrap());
+        assert_eq!(deployment.network.block_number(), Some(42));
+        assert_eq!(deployment.network.timestamp(), Some(1_600_000_000));
+        assert_eq!(deployment.network.deployer(), Some(Address::from_low_u64_be(1)));
+        assert_eq!(deployment.network.constructor_args(), Some(ethabi::encode(&[Token::Uint(U256::from(1000))])));
+        assert_eq!(deployment.network.bytecode_hash(), Some(H256::from(keccak256(&[0x60, 0x80]))));
+        assert_eq!(contract.get_network("5777"), Some(&deployment.network));
+
+        let requests = node.requests();
+        let (_, params) = requests.iter().find(|(m, _)| m == "eth_sendTransaction").unwrap();
+        assert_eq!(params[0]["from"], "0x0000000000000000000000000000000000000001");
+        assert!(params[0]["data"].as_str().unwrap().starts_with("0x6080604052"));
+        assert!(params[0].get("to").is_none());
+    }
+
+    #[test]
+    fn test_deploy_with_wallet() {
+        let node = node("0x1");
+        let client = Client::new(&node.url()).unwrap();
+        let wallet = Wallet::from_secret(&"46".repeat(32).from_hex::<Vec<u8>>().unwrap()).unwrap();
+        let deployer = wallet.address();
+        let mut contract = contract();
+
+        let deployment = Deployer::new(&client)
+            .wallet(wallet)
+            .deploy(&mut contract, &[Token::Uint(U256::from(1000))])
+            .unwrap();
+        assert_eq!(deployment.network.deployer(), Some(deployer));
+
+        let methods = node.requests().into_iter().map(|(m, _)| m).collect::<Vec<String>>();
+        assert!(methods.contains(&"eth_sendRawTransaction".to_owned()));
+        assert!(methods.contains(&"eth_estimateGas".to_owned()));
+        assert!(!methods.contains(&"eth_sendTransaction".to_owned()));
+        assert!(!methods.contains(&"eth_accounts".to_owned()));
+    }
+
+    #[test]
+    fn test_deploy_from_hardhat_artifact() {
+        let node = node("0x1");
+        let client = Client::new(&node.url()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = contract().write_to_dir_as(dir.path(), Format::Hardhat, true).unwrap();
+
+        let mut contract = Contract::read_from_file(&path).unwrap();
+        Deployer::new(&client).deploy(&mut contract, &[Token::Uint(U256::from(1000))]).unwrap();
+        contract.update_file(&path).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(Format::detect(&json), Format::Hardhat);
+        let deployed = Contract::read_from_file(&path).unwrap();
+        assert_eq!(deployed.get_address("5777"), Some(ADDRESS[2..].parse().unwrap()));
+        assert_eq!(deployed.networks, contract.networks);
+
+        // Rebuilding keeps the deployments
+        self::contract().write_to_dir_as(dir.path(), Format::Hardhat, true).unwrap();
+        assert_eq!(Contract::read_from_dir(dir.path(), "Token").unwrap().networks, contract.networks);
+    }
+
+    #[test]
+    fn test_failed_deploy() {
+        let node = node("0x0");
+        let client = Client::new(&node.url()).unwrap();
+        let mut contract = contract();
+
+        let err = Deployer::new(&client).deploy(&mut contract, &[Token::Uint(U256::from(1000))]).unwrap_err();
+        assert!(err.to_string().starts_with("The deployment of Token reverted"));
+        assert!(contract.networks.is_empty());
+
+        let err = Deployer::new(&client).deploy(&mut contract, &[]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_transact() {
+        let mined = node("0x1");
+        let client = Client::new(&mined.url()).unwrap();
+        let to = ADDRESS[2..].parse().unwrap();
+
+        let receipt = Deployer::new(&client)
+            .value(U256::from(5))
+            .transact(to, vec![0xca, 0xfe])
+            .unwrap();
+        assert_eq!(receipt.block_number.as_u64(), 42);
+
+        let requests = mined.requests();
+        let (_, params) = requests.iter().find(|(m, _)| m == "eth_sendTransaction").unwrap();
+        assert_eq!(params[0]["to"], ADDRESS);
+        assert_eq!(params[0]["data"], "0xcafe");
+        assert_eq!(params[0]["value"], "0x5");
+
+        let reverting = node("0x0");
+        let client = Client::new(&reverting.url()).unwrap();
+        let err = Deployer::new(&client).transact(to, vec![0xca, 0xfe]).unwrap_err();
+        assert!(err.to_string().ends_with("reverted"));
+    }
+}
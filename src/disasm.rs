@@ -0,0 +1,320 @@
+//! Disassembler of EVM bytecode.
+//!
+//! The code is assumed to end at the first `INVALID` right after an
+//! instruction that halts or jumps, which is where solc appends the runtime
+//! code to the creation code and the metadata to the runtime code. Anything
+//! after it is kept as data.
+
+use crate::linker;
+use crate::metadata::{self, Metadata};
+use rustc_hex::{FromHex, FromHexError, ToHex};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+const JUMPDEST: u8 = 0x5b;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const EQ: u8 = 0x14;
+const PUSH4: u8 = 0x63;
+const INVALID: u8 = 0xfe;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    /// Pushed bytes of the `PUSH` instructions, empty for the others
+    pub immediate: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disassembly {
+    pub instructions: Vec<Instruction>,
+    /// Bytes after the code, e.g. the runtime code within the creation code
+    pub data: Vec<u8>,
+    pub metadata: Option<Metadata>,
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> String {
+        match self.opcode {
+            0x5f..=0x7f => format!("PUSH{}", self.opcode - 0x5f),
+            0x80..=0x8f => format!("DUP{}", self.opcode - 0x7f),
+            0x90..=0x9f => format!("SWAP{}", self.opcode - 0x8f),
+            0xa0..=0xa4 => format!("LOG{}", self.opcode - 0xa0),
+            opcode => mnemonic(opcode).map(str::to_owned).unwrap_or_else(|| format!("UNKNOWN_0x{:02x}", opcode)),
+        }
+    }
+
+    /// Value pushed as a number, when it fits a `usize`
+    fn value(&self) -> Option<usize> {
+        if self.immediate.len() > std::mem::size_of::<usize>() {
+            return None;
+        }
+        Some(self.immediate.iter().fold(0, |acc, &b| (acc << 8) | b as usize))
+    }
+
+    fn halts(&self) -> bool {
+        // STOP, JUMP, RETURN, REVERT, INVALID and SELFDESTRUCT
+        matches!(self.opcode, 0x00 | JUMP | 0xf3 | 0xfd | INVALID | 0xff)
+    }
+}
+
+/// Disassembles the hex encoded bytecode, placeholders of libraries are read
+/// as the zero address
+pub fn disassemble(bin: &str) -> Result<Disassembly, FromHexError> {
+    let (code, metadata) = match metadata::split(bin) {
+        Some((code, _)) => (code, metadata::from_bytecode(bin)),
+        None => (bin, None),
+    };
+
+    let mut code = code.trim_start_matches("0x").to_owned();
+    for reference in linker::find_references(&code, &HashMap::new()) {
+        code = code.replace(&reference.placeholder, &"0".repeat(40));
+    }
+    let bytes: Vec<u8> = code.from_hex()?;
+
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut pc = 0;
+    while pc < bytes.len() {
+        let opcode = bytes[pc];
+        let size = match opcode {
+            0x60..=0x7f => (opcode - 0x5f) as usize,
+            _ => 0,
+        };
+        // Truncated pushes are padded with zeros by the EVM
+        let mut immediate = bytes[pc + 1..std::cmp::min(pc + 1 + size, bytes.len())].to_vec();
+        immediate.resize(size, 0);
+
+        let end_of_code = opcode == INVALID && matches!(instructions.last(), Some(i) if i.halts());
+        instructions.push(Instruction { pc, opcode, immediate });
+        pc += 1 + size;
+        if end_of_code {
+            break;
+        }
+    }
+
+    Ok(Disassembly {
+        instructions,
+        data: bytes[std::cmp::min(pc, bytes.len())..].to_vec(),
+        metadata,
+    })
+}
+
+/// Comments of the instructions keyed by pc: the destinations of the jumps,
+/// where the jumps to each `JUMPDEST` come from, and the functions of the
+/// dispatcher given their signatures and selectors
+pub fn annotations(instructions: &[Instruction], selectors: &[(String, [u8; 4])]) -> BTreeMap<usize, String> {
+    let jumpdests = instructions.iter()
+        .filter(|i| i.opcode == JUMPDEST)
+        .map(|i| i.pc)
+        .collect::<Vec<usize>>();
+
+    // `PUSH4 selector EQ PUSH dest JUMPI` in the dispatcher
+    let mut functions: HashMap<usize, &str> = HashMap::new();
+    let mut annotations: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for window in instructions.windows(4) {
+        if let [push4, eq, push, jumpi] = window {
+            let signature = selectors.iter().find(|(_, s)| push4.opcode == PUSH4 && push4.immediate == s);
+            if let (Some((signature, _)), EQ, JUMPI, Some(dest)) = (signature, eq.opcode, jumpi.opcode, push.value()) {
+                functions.insert(dest, signature);
+                annotations.entry(push4.pc).or_default().push(signature.clone());
+            }
+        }
+    }
+
+    let mut sources: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for pair in instructions.windows(2) {
+        if let [push, jump] = pair {
+            if !matches!(jump.opcode, JUMP | JUMPI) || push.immediate.is_empty() {
+                continue;
+            }
+            if let Some(dest) = push.value().filter(|dest| jumpdests.contains(dest)) {
+                let comment = match functions.get(&dest) {
+                    Some(function) => format!("-> 0x{:04x} ({})", dest, function),
+                    None => format!("-> 0x{:04x}", dest),
+                };
+                annotations.entry(jump.pc).or_default().push(comment);
+                sources.entry(dest).or_default().push(jump.pc);
+            }
+        }
+    }
+
+    for (dest, jumps) in sources.into_iter() {
+        let jumps = jumps.iter().map(|pc| format!("0x{:04x}", pc)).collect::<Vec<String>>();
+        let comment = match functions.get(&dest) {
+            Some(function) => format!("{} <- {}", function, jumps.join(", ")),
+            None => format!("<- {}", jumps.join(", ")),
+        };
+        annotations.entry(dest).or_default().push(comment);
+    }
+
+    annotations.into_iter().map(|(pc, comments)| (pc, comments.join("; "))).collect()
+}
+
+/// One instruction per line, followed by the data and metadata
+pub fn format(disassembly: &Disassembly, selectors: &[(String, [u8; 4])]) -> String {
+    let annotations = annotations(&disassembly.instructions, selectors);
+    let mut out = String::new();
+    for instruction in disassembly.instructions.iter() {
+        let mut line = format!("{:04x}: {}", instruction.pc, instruction.mnemonic());
+        if !instruction.immediate.is_empty() {
+            line = format!("{} 0x{}", line, instruction.immediate.to_hex::<String>());
+        }
+        match annotations.get(&instruction.pc) {
+            Some(comment) => writeln!(out, "{:<48} ; {}", line, comment),
+            None => writeln!(out, "{}", line),
+        }.expect("Writing to a String can't fail");
+    }
+
+    let end = disassembly.instructions.last().map_or(0, |i| i.pc + 1 + i.immediate.len());
+    if !disassembly.data.is_empty() {
+        writeln!(out, "{:04x}: data ({} bytes)", end, disassembly.data.len()).expect("Writing to a String can't fail");
+    }
+    if let Some(metadata) = disassembly.metadata.as_ref() {
+        let hash = metadata.hash.as_ref().map(|h| h.to_string()).unwrap_or_default();
+        let solc = metadata.solc.as_deref().unwrap_or("unknown");
+        writeln!(out, "metadata: {} solc {}", hash, solc).expect("Writing to a String can't fail");
+    }
+    out
+}
+
+fn mnemonic(opcode: u8) -> Option<&'static str> {
+    Some(match opcode {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "SHA3",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "DIFFICULTY",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dispatcher of `setCompleted(uint256)` followed by the metadata of solc 0.6.2
+    const RUNTIME: &str = "6080604052348015600f57600080fd5b50600436106028576000\
+        3560e01c8063fdacd57614602d575b600080fd5b005bfe\
+        a2646970667358221220ec52c46cd904fdc3f6ffdb72721846239a5bb061487afb8d1ba689f6b12b664564736f6c63430006020033";
+
+    #[test]
+    fn test_disassemble() {
+        let disassembly = disassemble(RUNTIME).unwrap();
+        let instructions = &disassembly.instructions;
+
+        assert_eq!(instructions[0], Instruction { pc: 0, opcode: 0x60, immediate: vec![0x80] });
+        assert_eq!(instructions[0].mnemonic(), "PUSH1");
+        assert_eq!(instructions[2].mnemonic(), "MSTORE");
+        assert_eq!(instructions.last().unwrap().mnemonic(), "INVALID");
+        assert!(disassembly.data.is_empty());
+        assert_eq!(disassembly.metadata.unwrap().solc.as_deref(), Some("0.6.2"));
+    }
+
+    #[test]
+    fn test_data_section() {
+        // Constructor returning the code after it
+        let disassembly = disassemble("6080604052f3fe6080604052").unwrap();
+        assert_eq!(disassembly.instructions.len(), 5);
+        assert_eq!(disassembly.data, vec![0x60, 0x80, 0x60, 0x40, 0x52]);
+
+        // INVALID reached by a jump is still code
+        let disassembly = disassemble("5bfe00").unwrap();
+        assert_eq!(disassembly.instructions.len(), 3);
+        assert!(disassembly.data.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_push_and_unknown() {
+        let disassembly = disassemble("0c61ff").unwrap();
+        assert_eq!(disassembly.instructions[0].mnemonic(), "UNKNOWN_0x0c");
+        assert_eq!(disassembly.instructions[1].immediate, vec![0xff, 0x00]);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let selectors = vec![("setCompleted(uint256)".to_owned(), [0xfd, 0xac, 0xd5, 0x76])];
+        let disassembly = disassemble(RUNTIME).unwrap();
+        let output = format(&disassembly, &selectors);
+
+        assert!(output.lines().any(|l| l.starts_with("001f: PUSH4 0xfdacd576") && l.ends_with("; setCompleted(uint256)")));
+        assert!(output.lines().any(|l| l.starts_with("0027: JUMPI") && l.ends_with("; -> 0x002d (setCompleted(uint256))")));
+        assert!(output.lines().any(|l| l.starts_with("002d: JUMPDEST") && l.ends_with("; setCompleted(uint256) <- 0x0027")));
+        assert!(output.lines().any(|l| l.starts_with("000a: JUMPI") && l.ends_with("; -> 0x000f")));
+        assert!(output.ends_with("metadata: ipfs://QmeFA8GB1yehR2J2RwAQpKqvZ4TtZBK5ej2EKw4XkK1Ktp solc 0.6.2\n"));
+    }
+}
@@ -0,0 +1,385 @@
+//! In-process EVM to deploy and call compiled contracts without a node, e.g.
+//! to test them from Rust. Only built with the `evm` feature.
+
+use crate::abi::{Abi, Function, Token};
+use crate::contract::{Address, Contract};
+use crate::rpc::ReceiptLog;
+use ethabi::ParamType;
+use ethereum_types::{H256, U256};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{self, AccountInfo, Bytes, ExecutionResult, Output, TxKind};
+use revm::{DatabaseCommit, DatabaseRef, Evm};
+use rustc_hex::{FromHex, ToHex};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Gas limit of the transactions and of the blocks
+const GAS_LIMIT: u64 = 30_000_000;
+const CHAIN_ID: u64 = 1337;
+
+/// Chain living in memory, every transaction is mined in a block of its own
+pub struct LocalChain {
+    db: CacheDB<EmptyDB>,
+    accounts: Vec<Address>,
+    sender: Address,
+    block_number: u64,
+    timestamp: u64,
+    /// Addresses of the contracts deployed so far, used to link libraries
+    deployed: HashMap<String, Address>,
+}
+
+/// Outcome of a successful transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    /// Address of the created contract
+    pub address: Option<Address>,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    pub logs: Vec<ReceiptLog>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionError {
+    /// `REVERT` with its data, e.g. an `Error(string)` or a custom error
+    Revert { data: Vec<u8>, gas_used: u64 },
+    /// Out of gas, invalid opcode... all the gas is spent
+    Halt { reason: String, gas_used: u64 },
+    /// The transaction couldn't be built or was rejected before running
+    Invalid(String),
+}
+
+impl LocalChain {
+    /// Chain with ten accounts holding 10000 ether, the first one sends the
+    /// transactions
+    pub fn new() -> Self {
+        let mut db = CacheDB::new(EmptyDB::default());
+        let accounts = (1..=10).map(|i| Address::from_low_u64_be(0x1000 + i)).collect::<Vec<Address>>();
+        let balance = primitives::U256::from(10_000u64) * primitives::U256::from(10u64).pow(primitives::U256::from(18u64));
+        for account in accounts.iter() {
+            db.insert_account_info(to_revm_address(*account), AccountInfo { balance, ..AccountInfo::default() });
+        }
+
+        Self {
+            db,
+            sender: accounts[0],
+            accounts,
+            block_number: 0,
+            timestamp: 1_600_000_000,
+            deployed: HashMap::new(),
+        }
+    }
+
+    /// Funded accounts
+    pub fn accounts(&self) -> &[Address] {
+        &self.accounts
+    }
+
+    pub fn sender(&self) -> Address {
+        self.sender
+    }
+
+    /// Sender of the next transactions and calls
+    pub fn set_sender(&mut self, sender: Address) {
+        self.sender = sender;
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// Deploys the contract, its libraries must have been deployed before
+    pub fn deploy(&mut self, contract: &Contract, args: &[Token]) -> Result<Execution, ExecutionError> {
+        let bin = contract.link(&self.deployed)
+            .map_err(|e| ExecutionError::Invalid(format!("{}: {}", contract.name, e)))?;
+        let code: Vec<u8> = bin.from_hex()
+            .map_err(|e| ExecutionError::Invalid(format!("{}: {}", contract.name, e)))?;
+        if code.is_empty() {
+            return Err(ExecutionError::Invalid(format!("{} has no bytecode, is it abstract or an interface?", contract.name)));
+        }
+
+        let data = match contract.abi.iter().find_map(|abi| match abi {
+            Abi::Constructor(constructor) => Some(constructor),
+            _ => None,
+        }) {
+            Some(constructor) => constructor.encode(&code, args)
+                .map_err(|e| ExecutionError::Invalid(format!("Invalid arguments of the constructor of {}: {}", contract.name, e)))?,
+            None if args.is_empty() => code,
+            None => return Err(ExecutionError::Invalid(format!("{} has no constructor taking arguments", contract.name))),
+        };
+
+        let execution = self.transact(None, data, U256::zero())?;
+        if let Some(address) = execution.address {
+            self.deployed.insert(contract.name.clone(), address);
+        }
+        Ok(execution)
+    }
+
+    /// Runs a function without keeping its changes and decodes its outputs
+    pub fn call(&self, address: Address, function: &Function, args: &[Token]) -> Result<Vec<Token>, ExecutionError> {
+        let data = function.encode_input(args)
+            .map_err(|e| ExecutionError::Invalid(format!("Invalid arguments of {}: {}", function.name, e)))?;
+        let execution = self.call_raw(address, data)?;
+        function.decode_output(&execution.output)
+            .map_err(|e| ExecutionError::Invalid(format!("Couldn't decode the output of {}: {}", function.name, e)))
+    }
+
+    /// Sends a transaction calling the function
+    pub fn send(&mut self, address: Address, function: &Function, args: &[Token]) -> Result<Execution, ExecutionError> {
+        self.send_value(address, function, args, U256::zero())
+    }
+
+    /// Sends a transaction calling a payable function with `value` wei
+    pub fn send_value(&mut self, address: Address, function: &Function, args: &[Token], value: U256) -> Result<Execution, ExecutionError> {
+        let data = function.encode_input(args)
+            .map_err(|e| ExecutionError::Invalid(format!("Invalid arguments of {}: {}", function.name, e)))?;
+        self.transact(Some(address), data, value)
+    }
+
+    /// Runs raw calldata without keeping its changes
+    pub fn call_raw(&self, address: Address, data: Vec<u8>) -> Result<Execution, ExecutionError> {
+        let mut evm = Evm::builder()
+            .with_ref_db(&self.db)
+            .modify_cfg_env(|cfg| cfg.chain_id = CHAIN_ID)
+            .modify_block_env(|block| self.block_env(block))
+            .modify_tx_env(|tx| self.tx_env(tx, Some(address), data, U256::zero()))
+            .build();
+        let result = evm.transact().map_err(|e| ExecutionError::Invalid(e.to_string()))?;
+        execution(result.result)
+    }
+
+    /// Mines a transaction creating a contract when `to` is `None`
+    pub fn transact(&mut self, to: Option<Address>, data: Vec<u8>, value: U256) -> Result<Execution, ExecutionError> {
+        self.block_number += 1;
+        self.timestamp += 12;
+
+        let (result, state) = {
+            let mut evm = Evm::builder()
+                .with_ref_db(&self.db)
+                .modify_cfg_env(|cfg| cfg.chain_id = CHAIN_ID)
+                .modify_block_env(|block| self.block_env(block))
+                .modify_tx_env(|tx| self.tx_env(tx, to, data, value))
+                .build();
+            let result = evm.transact().map_err(|e| ExecutionError::Invalid(e.to_string()))?;
+            (result.result, result.state)
+        };
+        // Reverted transactions still pay for their gas and bump the nonce
+        self.db.commit(state);
+        execution(result)
+    }
+
+    /// Wei held by the account
+    pub fn balance(&self, address: Address) -> U256 {
+        self.account(address).map(|info| from_revm_u256(info.balance)).unwrap_or_default()
+    }
+
+    /// Runtime code at `address`
+    pub fn code(&self, address: Address) -> Vec<u8> {
+        self.account(address)
+            .and_then(|info| match info.code {
+                Some(code) => Some(code),
+                None => self.db.code_by_hash_ref(info.code_hash).ok(),
+            })
+            .map(|code| code.original_bytes().to_vec())
+            .unwrap_or_default()
+    }
+
+    fn account(&self, address: Address) -> Option<AccountInfo> {
+        self.db.basic_ref(to_revm_address(address)).ok().flatten()
+    }
+
+    fn block_env(&self, block: &mut primitives::BlockEnv) {
+        block.number = primitives::U256::from(self.block_number);
+        block.timestamp = primitives::U256::from(self.timestamp);
+        block.gas_limit = primitives::U256::from(GAS_LIMIT);
+        block.basefee = primitives::U256::ZERO;
+    }
+
+    fn tx_env(&self, tx: &mut primitives::TxEnv, to: Option<Address>, data: Vec<u8>, value: U256) {
+        tx.caller = to_revm_address(self.sender);
+        tx.transact_to = match to {
+            Some(to) => TxKind::Call(to_revm_address(to)),
+            None => TxKind::Create,
+        };
+        tx.data = Bytes::from(data);
+        tx.value = to_revm_u256(value);
+        tx.gas_limit = GAS_LIMIT;
+        tx.gas_price = primitives::U256::ZERO;
+        tx.chain_id = Some(CHAIN_ID);
+    }
+}
+
+impl Default for LocalChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionError {
+    pub fn gas_used(&self) -> Option<u64> {
+        match self {
+            ExecutionError::Revert { gas_used, .. } | ExecutionError::Halt { gas_used, .. } => Some(*gas_used),
+            ExecutionError::Invalid(_) => None,
+        }
+    }
+
+    /// Data of a revert, the selector of a custom error can be looked up with
+    /// `Contract::get_error_by_selector`
+    pub fn revert_data(&self) -> Option<&[u8]> {
+        match self {
+            ExecutionError::Revert { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecutionError::Revert { data, .. } => match revert_reason(data) {
+                Some(reason) => write!(f, "Reverted: {}", reason),
+                None if data.is_empty() => write!(f, "Reverted"),
+                None => write!(f, "Reverted with 0x{}", data.to_hex::<String>()),
+            },
+            ExecutionError::Halt { reason, .. } => write!(f, "Halted: {}", reason),
+            ExecutionError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+fn execution(result: ExecutionResult) -> Result<Execution, ExecutionError> {
+    match result {
+        ExecutionResult::Success { gas_used, logs, output, .. } => {
+            let (address, output) = match output {
+                Output::Create(output, address) => (address.map(from_revm_address), output),
+                Output::Call(output) => (None, output),
+            };
+            let logs = logs.into_iter()
+                .map(|log| ReceiptLog {
+                    address: from_revm_address(log.address),
+                    topics: log.data.topics().iter().map(|topic| H256::from_slice(topic.as_slice())).collect(),
+                    data: log.data.data.to_vec(),
+                })
+                .collect();
+            Ok(Execution { address, output: output.to_vec(), gas_used, logs })
+        },
+        ExecutionResult::Revert { gas_used, output } => Err(ExecutionError::Revert { data: output.to_vec(), gas_used }),
+        ExecutionResult::Halt { reason, gas_used } => Err(ExecutionError::Halt { reason: format!("{:?}", reason), gas_used }),
+    }
+}
+
+/// Message of `Error(string)` or code of `Panic(uint256)`
+fn revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    match data[..4] {
+        [0x08, 0xc3, 0x79, 0xa0] => match ethabi::decode(&[ParamType::String], &data[4..]).ok()?.pop()? {
+            Token::String(message) => Some(message),
+            _ => None,
+        },
+        [0x4e, 0x48, 0x7b, 0x71] => match ethabi::decode(&[ParamType::Uint(256)], &data[4..]).ok()?.pop()? {
+            Token::Uint(code) => Some(format!("panic 0x{:02x}", code)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_revm_address(address: Address) -> primitives::Address {
+    primitives::Address::from_slice(address.as_bytes())
+}
+
+fn from_revm_address(address: primitives::Address) -> Address {
+    Address::from_slice(address.as_slice())
+}
+
+fn to_revm_u256(value: U256) -> primitives::U256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    primitives::U256::from_be_bytes(bytes)
+}
+
+fn from_revm_u256(value: primitives::U256) -> U256 {
+    U256::from_big_endian(&value.to_be_bytes::<32>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+
+    /// Creation code returning `runtime`
+    fn contract(abi: &str, runtime: &str) -> Contract {
+        let len = runtime.len() / 2;
+        let bin = format!("60{:02x}600c60003960{:02x}6000f3{}", len, len, runtime);
+        Contract::new("Test".to_owned(), human::parse_all(abi).unwrap(), bin)
+    }
+
+    fn function(contract: &Contract) -> Function {
+        contract.get_methods().into_iter().next().unwrap().1
+    }
+
+    #[test]
+    fn test_deploy_and_call() {
+        // Returns 42 whatever the calldata
+        let contract = contract("function answer() view returns (uint256)", "602a60005260206000f3");
+        let mut chain = LocalChain::new();
+
+        let deployment = chain.deploy(&contract, &[]).unwrap();
+        let address = deployment.address.unwrap();
+        assert!(deployment.gas_used > 53_000);
+        assert_eq!(chain.code(address), "602a60005260206000f3".from_hex::<Vec<u8>>().unwrap());
+        assert_eq!(chain.block_number(), 1);
+
+        let outputs = chain.call(address, &function(&contract), &[]).unwrap();
+        assert_eq!(outputs, vec![Token::Uint(U256::from(42))]);
+        assert_eq!(chain.block_number(), 1);
+    }
+
+    #[test]
+    fn test_send_logs() {
+        // Emits the calldata in a log with the topic 0xaa..aa
+        let runtime = format!("366000600037{}{}366000a100", "7f", "aa".repeat(32));
+        let contract = contract("function ping(uint256 value) payable", &runtime);
+        let mut chain = LocalChain::new();
+        let address = chain.deploy(&contract, &[]).unwrap().address.unwrap();
+
+        let sender = chain.accounts()[1];
+        chain.set_sender(sender);
+        let execution = chain.send_value(address, &function(&contract), &[Token::Uint(U256::from(7))], U256::from(100)).unwrap();
+        assert!(execution.gas_used > 21_000);
+        assert_eq!(execution.logs.len(), 1);
+        assert_eq!(execution.logs[0].address, address);
+        assert_eq!(execution.logs[0].topics, vec![H256::repeat_byte(0xaa)]);
+        assert_eq!(execution.logs[0].data[4..], ethabi::encode(&[Token::Uint(U256::from(7))])[..]);
+        assert_eq!(chain.balance(address), U256::from(100));
+    }
+
+    #[test]
+    fn test_revert() {
+        // Reverts with Error("nope")
+        let data = [&[0x08, 0xc3, 0x79, 0xa0][..], &ethabi::encode(&[Token::String("nope".to_owned())])].concat();
+        let runtime = format!("7f{}6000527f{}6020527f{}6040527f{}60605260646000fd",
+            data[..32].to_hex::<String>(), data[32..64].to_hex::<String>(),
+            data[64..96].to_hex::<String>(), hex_word(&data[96..]));
+        let contract = contract("function fail()", &runtime);
+        let mut chain = LocalChain::new();
+        let address = chain.deploy(&contract, &[]).unwrap().address.unwrap();
+
+        let err = chain.send(address, &function(&contract), &[]).unwrap_err();
+        assert_eq!(err.revert_data(), Some(&data[..]));
+        assert_eq!(err.to_string(), "Reverted: nope");
+        assert!(err.gas_used().unwrap() > 21_000);
+
+        let err = chain.deploy(&contract, &[Token::Bool(true)]).unwrap_err();
+        assert!(matches!(err, ExecutionError::Invalid(_)));
+    }
+
+    fn hex_word(bytes: &[u8]) -> String {
+        let mut word = bytes.to_vec();
+        word.resize(32, 0);
+        word.to_hex()
+    }
+}
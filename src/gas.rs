@@ -0,0 +1,383 @@
+//! Snapshots of the gas estimates of the contracts, written one entry per line
+//! and sorted so they can be committed and compared between builds, e.g.
+//!
+//! ```text
+//! Token:balanceOf(address) 1257
+//! Token:construction 79093
+//! Token:transfer(address,uint256) infinite
+//! ```
+//!
+//! and reports of the estimates as Markdown, CSV or JSON.
+
+use crate::abi::Abi;
+use crate::contract::{Contract, Gas};
+#[cfg(feature = "evm")]
+use crate::testing::TestResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Name of the construction entry of each contract
+pub const CONSTRUCTION: &str = "construction";
+
+/// Estimates keyed by `Contract:signature`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Snapshot {
+    pub entries: BTreeMap<String, Gas>,
+}
+
+/// Estimates of a set of contracts, sorted by contract and function
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct GasReport {
+    pub rows: Vec<GasRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GasRow {
+    pub contract: String,
+    /// Signature of the function, or `construction`
+    pub function: String,
+    /// From the ABI, `internal` for the internal functions
+    pub mutability: String,
+    pub gas: Gas,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasChange {
+    pub key: String,
+    /// `None` when the entry was added
+    pub before: Option<Gas>,
+    /// `None` when the entry was removed
+    pub after: Option<Gas>,
+}
+
+impl Snapshot {
+    /// Construction, external and internal estimates of the contracts, those
+    /// without estimates (e.g. interfaces) are left out
+    pub fn from_contracts(contracts: &[Contract]) -> Self {
+        let mut entries = BTreeMap::new();
+        for contract in contracts.iter() {
+            if let Some(gas) = contract.gas_estimates.as_ref() {
+                entries.insert(format!("{}:{}", contract.name, CONSTRUCTION), gas.construction.total());
+                for (signature, gas) in gas.external.iter().chain(gas.internal.iter()) {
+                    entries.insert(format!("{}:{}", contract.name, signature), *gas);
+                }
+            }
+        }
+        Self { entries }
+    }
+
+    /// Gas used by the Solidity tests that passed, keyed by their contract
+    /// and signature
+    #[cfg(feature = "evm")]
+    pub fn from_tests(results: &[TestResult]) -> Self {
+        let mut snapshot = Self::default();
+        for result in results.iter().filter(|r| r.passed()) {
+            if let Some(gas) = result.gas_used {
+                snapshot.record(&result.contract, &result.test, gas);
+            }
+        }
+        snapshot
+    }
+
+    /// Records the gas measured for a function, e.g. on an `evm::LocalChain`,
+    /// in place of the estimate. `construction` is the key of the deployment
+    pub fn record(&mut self, contract: &str, signature: &str, gas: u64) {
+        self.entries.insert(format!("{}:{}", contract, signature), Gas::Finite(gas));
+    }
+
+    /// Entries that differ from `before`, in the order of the keys
+    pub fn compare(&self, before: &Snapshot) -> Vec<GasChange> {
+        let mut keys = self.entries.keys().chain(before.entries.keys()).collect::<Vec<&String>>();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .map(|key| GasChange {
+                key: key.clone(),
+                before: before.entries.get(key).copied(),
+                after: self.entries.get(key).copied(),
+            })
+            .filter(|change| change.before != change.after)
+            .collect()
+    }
+}
+
+impl GasReport {
+    pub fn new(contracts: &[Contract]) -> Self {
+        let mut rows = Vec::new();
+        for contract in contracts.iter() {
+            let gas = match contract.gas_estimates.as_ref() {
+                Some(gas) => gas,
+                None => continue,
+            };
+
+            let constructor = contract.abi.iter().find_map(|abi| match abi {
+                Abi::Constructor(constructor) => Some(constructor.stateMutability.clone()),
+                _ => None,
+            });
+            rows.push(GasRow {
+                contract: contract.name.clone(),
+                function: CONSTRUCTION.to_owned(),
+                mutability: constructor.unwrap_or_else(|| "nonpayable".to_owned()),
+                gas: gas.construction.total(),
+            });
+
+            for (signature, gas) in gas.external.iter() {
+                rows.push(GasRow {
+                    contract: contract.name.clone(),
+                    function: signature.clone(),
+                    mutability: contract.get_function(signature)
+                        .map(|f| f.stateMutability.clone())
+                        .unwrap_or_default(),
+                    gas: *gas,
+                });
+            }
+            for (signature, gas) in gas.internal.iter() {
+                rows.push(GasRow {
+                    contract: contract.name.clone(),
+                    function: signature.clone(),
+                    mutability: "internal".to_owned(),
+                    gas: *gas,
+                });
+            }
+        }
+
+        rows.sort_by(|a, b| (&a.contract, &a.function).cmp(&(&b.contract, &b.function)));
+        Self { rows }
+    }
+
+    /// Keeps the rows of the given contracts
+    pub fn filter(mut self, contracts: &[impl AsRef<str>]) -> Self {
+        self.rows.retain(|row| contracts.iter().any(|c| c.as_ref() == row.contract));
+        self
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("| Contract | Function | Mutability | Gas |\n|---|---|---|---:|\n");
+        for row in self.rows.iter() {
+            out.push_str(&format!("| {} | `{}` | {} | {} |\n", row.contract, row.function, row.mutability, row.gas));
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("contract,function,mutability,gas\n");
+        for row in self.rows.iter() {
+            let fields = [&row.contract, &row.function, &row.mutability, &row.gas.to_string()]
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<String>>();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_json(&self, pretty_print: bool) -> String {
+        if pretty_print {
+            serde_json::to_string_pretty(&self.rows).unwrap()
+        } else {
+            serde_json::to_string(&self.rows).unwrap()
+        }
+    }
+}
+
+/// Quotes the field when it has commas, quotes or line breaks
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, gas) in self.entries.iter() {
+            writeln!(f, "{} {}", key, gas)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = BTreeMap::new();
+        for (number, line) in s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let (key, gas) = line.trim()
+                .rsplit_once(' ')
+                .ok_or_else(|| format!("Line {}: expected `Contract:signature gas`", number + 1))?;
+            let gas = gas.parse().map_err(|e| format!("Line {}: {}", number + 1, e))?;
+            entries.insert(key.trim().to_owned(), gas);
+        }
+        Ok(Self { entries })
+    }
+}
+
+impl GasChange {
+    /// Increase over the previous estimate, in percent. Infinite when the
+    /// estimate becomes infinite, `None` for added and removed entries or
+    /// when nothing can be compared
+    pub fn percent(&self) -> Option<f64> {
+        match (self.before?, self.after?) {
+            (Gas::Finite(before), Gas::Finite(after)) if before > 0 => {
+                Some((after as f64 - before as f64) * 100.0 / before as f64)
+            },
+            (Gas::Finite(_), Gas::Finite(after)) if after > 0 => Some(f64::INFINITY),
+            (Gas::Finite(_), Gas::Infinite) => Some(f64::INFINITY),
+            (Gas::Infinite, Gas::Finite(_)) => Some(f64::NEG_INFINITY),
+            _ => None,
+        }
+    }
+
+    /// Whether the estimate increased more than `threshold` percent
+    pub fn exceeds(&self, threshold: f64) -> bool {
+        matches!(self.percent(), Some(percent) if percent > threshold)
+    }
+}
+
+impl fmt::Display for GasChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.before, self.after) {
+            (None, Some(after)) => write!(f, "+ {} {}", self.key, after),
+            (Some(before), None) => write!(f, "- {} {}", self.key, before),
+            (Some(before), Some(after)) => {
+                write!(f, "~ {} {} -> {}", self.key, before, after)?;
+                match self.percent() {
+                    Some(percent) if percent.is_finite() => write!(f, " ({:+.2}%)", percent),
+                    _ => Ok(()),
+                }
+            },
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{ConstructionGas, GasEstimates};
+    use std::collections::HashMap;
+
+    fn contract(transfer: Gas) -> Contract {
+        let mut external = HashMap::new();
+        external.insert("transfer(address,uint256)".to_owned(), transfer);
+        external.insert("transfer(address,uint256,bytes)".to_owned(), Gas::Infinite);
+        let mut internal = HashMap::new();
+        internal.insert("_mint(address,uint256)".to_owned(), Gas::Finite(42));
+
+        let mut contract = Contract::new("Token".to_owned(), Vec::new(), String::new());
+        contract.gas_estimates = Some(GasEstimates {
+            construction: ConstructionGas { execution: Gas::Finite(93), code_deposit: Gas::Finite(79000) },
+            external,
+            internal,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let interface = Contract::new("IToken".to_owned(), Vec::new(), String::new());
+        let snapshot = Snapshot::from_contracts(&[contract(Gas::Finite(1200)), interface]);
+        let text = snapshot.to_string();
+        assert_eq!(text, "\
+            Token:_mint(address,uint256) 42\n\
+            Token:construction 79093\n\
+            Token:transfer(address,uint256) 1200\n\
+            Token:transfer(address,uint256,bytes) infinite\n");
+        assert_eq!(text.parse::<Snapshot>(), Ok(snapshot));
+
+        assert!("Token:construction lots".parse::<Snapshot>().is_err());
+        assert!("Token:construction".parse::<Snapshot>().is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = Snapshot::from_contracts(&[contract(Gas::Finite(1200))]);
+        assert!(before.compare(&before).is_empty());
+
+        let mut after = Snapshot::from_contracts(&[contract(Gas::Finite(1260))]);
+        after.entries.remove("Token:_mint(address,uint256)");
+        after.entries.insert("Token:burn(uint256)".to_owned(), Gas::Finite(500));
+
+        let changes = after.compare(&before);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].to_string(), "- Token:_mint(address,uint256) 42");
+        assert_eq!(changes[1].to_string(), "+ Token:burn(uint256) 500");
+        assert_eq!(changes[2].to_string(), "~ Token:transfer(address,uint256) 1200 -> 1260 (+5.00%)");
+        assert!(changes[2].exceeds(4.0));
+        assert!(!changes[2].exceeds(5.0));
+        assert!(!changes[0].exceeds(0.0));
+
+        let infinite = GasChange { key: String::new(), before: Some(Gas::Finite(1)), after: Some(Gas::Infinite) };
+        assert!(infinite.exceeds(1000.0));
+    }
+
+    #[test]
+    fn test_record() {
+        let mut snapshot = Snapshot::from_contracts(&[contract(Gas::Finite(1200))]);
+        snapshot.record("Token", "transfer(address,uint256)", 51234);
+        snapshot.record("Vault", CONSTRUCTION, 90000);
+
+        assert_eq!(snapshot.entries["Token:transfer(address,uint256)"], Gas::Finite(51234));
+        assert_eq!(snapshot.entries["Token:transfer(address,uint256,bytes)"], Gas::Infinite);
+        assert_eq!(snapshot.entries["Vault:construction"], Gas::Finite(90000));
+    }
+
+    #[cfg(feature = "evm")]
+    #[test]
+    fn test_from_tests() {
+        let result = |test: &str, gas_used, failure: Option<&str>| TestResult {
+            contract: "TokenTest".to_owned(),
+            test: test.to_owned(),
+            gas_used,
+            failure: failure.map(str::to_owned),
+        };
+        let snapshot = Snapshot::from_tests(&[
+            result("testTransfer()", Some(51234), None),
+            result("testBurn()", Some(30000), Some("Reverted")),
+        ]);
+        assert_eq!(snapshot.to_string(), "TokenTest:testTransfer() 51234\n");
+    }
+
+    #[test]
+    fn test_report() {
+        let mut token = contract(Gas::Finite(1200));
+        token.abi = crate::abi::Abi::from_json_array(r#"[
+            {"type":"constructor","inputs":[],"stateMutability":"payable"},
+            {"type":"function","name":"transfer","inputs":[{"name":"to","type":"address"},{"name":"value","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"}
+        ]"#).unwrap();
+        let mut other = contract(Gas::Finite(1));
+        other.name = "Other".to_owned();
+
+        let report = GasReport::new(&[token, other]).filter(&["Token"]);
+        assert_eq!(report.rows.len(), 4);
+        assert_eq!(report.rows[1], GasRow {
+            contract: "Token".to_owned(),
+            function: "construction".to_owned(),
+            mutability: "payable".to_owned(),
+            gas: Gas::Finite(79093),
+        });
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("| Contract | Function | Mutability | Gas |\n|---|---|---|---:|\n"));
+        assert!(markdown.contains("| Token | `_mint(address,uint256)` | internal | 42 |\n"));
+        assert!(markdown.contains("| Token | `transfer(address,uint256)` | nonpayable | 1200 |\n"));
+
+        assert_eq!(report.to_csv(), "\
+            contract,function,mutability,gas\n\
+            Token,\"_mint(address,uint256)\",internal,42\n\
+            Token,construction,payable,79093\n\
+            Token,\"transfer(address,uint256)\",nonpayable,1200\n\
+            Token,\"transfer(address,uint256,bytes)\",,infinite\n");
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json(false)).unwrap();
+        assert_eq!(json[3]["gas"], "infinite");
+        assert_eq!(json[2]["gas"], 1200);
+        assert_eq!(json[2]["function"], "transfer(address,uint256)");
+    }
+}
@@ -21,4 +21,45 @@ pub mod compiler;
 
 /// Contracts parsed from _solc_ output
 pub mod contract;
+
+/// Metadata appended by _solc_ to the bytecode
+pub mod metadata;
+
+/// Linking of the bytecode against external libraries
+pub mod linker;
+
+/// Artifact formats of Truffle, Hardhat and Foundry
+pub mod artifact;
+
+/// Inputs to verify the sources of the contracts
+pub mod verify;
+
+/// Typed bindings generated from the contracts
+pub mod bindgen;
+
+/// Disassembler of the bytecode
+pub mod disasm;
+
+/// Snapshots and reports of the gas estimates
+pub mod gas;
+
+/// JSON-RPC client of Ethereum nodes
+pub mod rpc;
+
+/// Keystores and signing of transactions
+pub mod wallet;
+
+/// Deployment of the contracts over JSON-RPC
+pub mod deploy;
+
+/// Truffle-like migrations tracked by the `Migrations` contract
+pub mod migrate;
+
+/// In-process EVM running the contracts without a node
+#[cfg(feature = "evm")]
+pub mod evm;
+
+/// Solidity tests run on the in-process EVM
+#[cfg(feature = "evm")]
+pub mod testing;
 mod solc;
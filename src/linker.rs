@@ -0,0 +1,208 @@
+//! Placeholders left by solc in the bytecode of contracts using external
+//! libraries, and their replacement by the addresses of the libraries.
+//!
+//! Since solc 0.5 the placeholders are `__$<hash>$__`, where the hash is the
+//! start of the keccak256 of the fully qualified name of the library, e.g.
+//! `contracts/Math.sol:Math`. Older versions put the (truncated) name itself
+//! between the underscores.
+
+use crate::contract::Address;
+use rustc_hex::ToHex;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use tiny_keccak::keccak256;
+
+/// Length of a placeholder in hex characters, the same as an address
+const PLACEHOLDER_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkReference {
+    /// Fully qualified name of the library when known, the text between the
+    /// underscores of the placeholder otherwise
+    pub name: String,
+    pub placeholder: String,
+    /// Byte offsets of the placeholders in the bytecode
+    pub offsets: Vec<usize>,
+}
+
+/// Libraries whose addresses weren't given
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkError {
+    pub unresolved: Vec<String>,
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unresolved libraries: {}", self.unresolved.join(", "))
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl LinkReference {
+    /// Whether `library` is the name, fully qualified or not, of the library
+    pub fn matches(&self, library: &str) -> bool {
+        self.name == library
+            || self.name.ends_with(&format!(":{}", library))
+            || self.placeholder == placeholder(library)
+    }
+
+    /// Address of the library among the given ones, a fully qualified name
+    /// wins over a bare one so `Math` and `contracts/Math.sol:Math` can both
+    /// be given
+    pub fn find_library<'a>(&self, libraries: &'a HashMap<String, Address>) -> Option<(&'a String, &'a Address)> {
+        libraries.iter()
+            .find(|(name, _)| **name == self.name || self.placeholder == placeholder(name))
+            .or_else(|| libraries.iter().find(|(name, _)| self.matches(name)))
+    }
+}
+
+/// Placeholder of solc >= 0.5 for the fully qualified name of a library
+pub fn placeholder(library: &str) -> String {
+    let hash: String = keccak256(library.as_bytes()).to_hex();
+    format!("__${}$__", &hash[..PLACEHOLDER_LEN - 6])
+}
+
+/// Placeholders in the hex encoded `bin`, `names` maps placeholders to the
+/// fully qualified names of the libraries, as listed by solc after the
+/// bytecode
+pub fn find_references(bin: &str, names: &HashMap<String, String>) -> Vec<LinkReference> {
+    let mut references: Vec<LinkReference> = Vec::new();
+    let mut position = 0;
+    while let Some(start) = bin[position..].find("__").map(|i| position + i) {
+        let placeholder = match bin.get(start..start + PLACEHOLDER_LEN) {
+            Some(p) if start % 2 == 0 && p.ends_with("__") => p,
+            _ => {
+                position = start + 1;
+                continue;
+            },
+        };
+
+        match references.iter_mut().find(|r| r.placeholder == placeholder) {
+            Some(reference) => reference.offsets.push(start / 2),
+            None => references.push(LinkReference {
+                name: names.get(placeholder)
+                    .cloned()
+                    .unwrap_or_else(|| placeholder.trim_matches('_').to_owned()),
+                placeholder: placeholder.to_owned(),
+                offsets: vec![start / 2],
+            }),
+        }
+        position = start + PLACEHOLDER_LEN;
+    }
+    references
+}
+
+/// Replaces the placeholders of the given libraries, returns the references
+/// left unresolved. Placeholders are replaced wherever they are and the
+/// offsets aren't used, so the references of the creation code also link the
+/// runtime code embedded in it
+pub fn link<'a>(
+    bin: &str,
+    references: &'a [LinkReference],
+    libraries: &HashMap<String, Address>,
+) -> (String, Vec<&'a LinkReference>) {
+    let mut bin = bin.to_owned();
+    let mut unresolved = Vec::new();
+    for reference in references.iter() {
+        match reference.find_library(libraries) {
+            Some((_, address)) => bin = bin.replace(&reference.placeholder, &address.as_bytes().to_hex::<String>()),
+            None => unresolved.push(reference),
+        }
+    }
+    (bin, unresolved)
+}
+
+/// Parses `Name=0x...` as given to `--libraries`
+pub fn parse_library(library: &str) -> Result<(String, Address), String> {
+    let mut parts = library.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(address)) if !name.is_empty() => address.trim_start_matches("0x")
+            .parse()
+            .map(|address| (name.to_owned(), address))
+            .map_err(|_| format!("Invalid address of {}: {}", name, address)),
+        _ => Err(format!("Expected Name=0x..., got {}", library)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATH: &str = "contracts/Math.sol:Math";
+
+    fn bin() -> String {
+        let math = placeholder(MATH);
+        format!("6080{}6000{}00__{:_<38}00", math, math, "Old.sol:Old")
+    }
+
+    #[test]
+    fn test_placeholder() {
+        assert_eq!(placeholder(MATH).len(), 40);
+        assert!(placeholder(MATH).starts_with("__$"));
+        assert!(placeholder(MATH).ends_with("$__"));
+    }
+
+    #[test]
+    fn test_find_references() {
+        let mut names = HashMap::new();
+        names.insert(placeholder(MATH), MATH.to_owned());
+
+        let references = find_references(&bin(), &names);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].name, MATH);
+        assert_eq!(references[0].offsets, vec![2, 24]);
+        assert_eq!(references[1].name, "Old.sol:Old");
+        assert_eq!(references[1].offsets, vec![45]);
+
+        // Without the names of solc only the hash is known
+        let references = find_references(&bin(), &HashMap::new());
+        assert_eq!(references[0].name, placeholder(MATH).trim_matches('_'));
+        assert!(references[0].matches(MATH));
+    }
+
+    #[test]
+    fn test_link() {
+        let references = find_references(&bin(), &HashMap::new());
+        let address: Address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+
+        let mut libraries = HashMap::new();
+        libraries.insert(MATH.to_owned(), address);
+        let (linked, unresolved) = link(&bin(), &references, &libraries);
+        assert_eq!(&linked[..44], "6080e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab");
+        assert_eq!(unresolved, vec![&references[1]]);
+
+        libraries.insert("Old".to_owned(), address);
+        let (linked, unresolved) = link(&bin(), &references, &libraries);
+        assert!(!linked.contains("__"));
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_link_qualified_first() {
+        let mut names = HashMap::new();
+        names.insert(placeholder(MATH), MATH.to_owned());
+        let references = find_references(&bin(), &names);
+        let address: Address = "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap();
+
+        let mut libraries = HashMap::new();
+        libraries.insert("Math".to_owned(), Address::from_low_u64_be(1));
+        libraries.insert(MATH.to_owned(), address);
+        libraries.insert("other/Math.sol:Math".to_owned(), Address::from_low_u64_be(2));
+        assert_eq!(references[0].find_library(&libraries), Some((&MATH.to_owned(), &address)));
+
+        libraries.remove(MATH);
+        assert_eq!(references[0].find_library(&libraries), Some((&"Math".to_owned(), &Address::from_low_u64_be(1))));
+    }
+
+    #[test]
+    fn test_parse_library() {
+        assert_eq!(
+            parse_library("Math=0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab"),
+            Ok(("Math".to_owned(), "e78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab".parse().unwrap()))
+        );
+        assert!(parse_library("Math").is_err());
+        assert!(parse_library("Math=0x1234").is_err());
+    }
+}
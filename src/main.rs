@@ -1,17 +1,21 @@
 mod build;
 mod config;
+mod commands;
 
-use clap::{Arg, App};
+use clap::{Arg, App, AppSettings};
 use std::path::Path;
 use config::Config;
+use tortilla::artifact::Format;
+use tortilla::linker;
 use build::{watch, build_to_stderr};
 use std::io::Result;
 
 fn main() -> Result<()> {
-    let matches = App::new("Tortilla")
+    let app = App::new("Tortilla")
         .version("0.1.0")
         .author("Erick Hdez <Erick.HernandezCuriel@mx.bosch.com>")
         .about("Solidity compiler")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("INPUTS")
              .help("Sets the input files/dirs to use")
              .required(true)
@@ -29,11 +33,67 @@ fn main() -> Result<()> {
              .short("p")
              .long("pretty")
              .help("Sets the JSON to be pretty printed"))
+        .arg(Arg::with_name("FORMAT")
+             .long("format")
+             .takes_value(true)
+             .possible_values(&["native", "truffle", "hardhat", "foundry"])
+             .default_value("native")
+             .help("Sets the layout of the artifacts"))
         .arg(Arg::with_name("GAS")
              .short("g")
              .long("gas")
              .help("Prints the gas estimates of the methods"))
-        .get_matches();
+        .arg(Arg::with_name("SIZES")
+             .long("sizes")
+             .help("Prints the sizes of the bytecode against the EIP-170 and EIP-3860 limits"))
+        .arg(Arg::with_name("FAIL_ON_SIZE")
+             .long("fail-on-size")
+             .help("Fails when a contract exceeds the size limits"))
+        .arg(Arg::with_name("PROXY")
+             .long("proxy")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .value_name("PROXY:IMPLEMENTATION")
+             .validator(|p| parse_proxy(&p).map(|_| ()))
+             .help("Warns about functions of IMPLEMENTATION shadowed by PROXY"))
+        .arg(Arg::with_name("LIBRARIES")
+             .long("libraries")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .value_name("NAME=ADDRESS")
+             .validator(|l| linker::parse_library(&l).map(|_| ()))
+             .help("Links the library NAME deployed at ADDRESS"))
+        .subcommand(commands::abi::subcommand())
+        .subcommand(commands::abi_diff::subcommand())
+        .subcommand(commands::bindgen::subcommand())
+        .subcommand(commands::verify_input::subcommand())
+        .subcommand(commands::disasm::subcommand())
+        .subcommand(commands::gas::subcommand())
+        .subcommand(commands::deploy::subcommand())
+        .subcommand(commands::call::subcommand())
+        .subcommand(commands::send::subcommand())
+        .subcommand(commands::migrate::subcommand());
+    #[cfg(feature = "evm")]
+    let app = app.subcommand(commands::test::subcommand());
+    let matches = app.get_matches();
+
+    match matches.subcommand() {
+        ("abi", Some(matches)) => return commands::abi::run(matches),
+        ("abi-diff", Some(matches)) => return commands::abi_diff::run(matches),
+        ("bindgen", Some(matches)) => return commands::bindgen::run(matches),
+        ("verify-input", Some(matches)) => return commands::verify_input::run(matches),
+        ("disasm", Some(matches)) => return commands::disasm::run(matches),
+        ("gas", Some(matches)) => return commands::gas::run(matches),
+        ("deploy", Some(matches)) => return commands::deploy::run(matches),
+        ("call", Some(matches)) => return commands::call::run(matches),
+        ("send", Some(matches)) => return commands::send::run(matches),
+        ("migrate", Some(matches)) => return commands::migrate::run(matches),
+        #[cfg(feature = "evm")]
+        ("test", Some(matches)) => return commands::test::run(matches),
+        _ => {},
+    }
 
     let inputs = filter_paths(matches.values_of_lossy("INPUTS").unwrap());
     if inputs.is_empty() {
@@ -43,18 +103,32 @@ fn main() -> Result<()> {
     let should_watch = matches.is_present("WATCH");
     let output = matches.value_of("OUTPUT").unwrap_or("");
     let pretty_print = matches.is_present("PRETTY_PRINT");
+    let format: Format = matches.value_of("FORMAT").unwrap().parse().unwrap();
     let gas = matches.is_present("GAS");
+    let sizes = matches.is_present("SIZES");
+    let fail_on_size = matches.is_present("FAIL_ON_SIZE");
+    let proxies = matches.values_of("PROXY")
+        .map(|p| p.map(|p| parse_proxy(p).unwrap()).collect())
+        .unwrap_or_default();
+    let libraries = matches.values_of("LIBRARIES")
+        .map(|l| l.map(|l| linker::parse_library(l).unwrap()).collect())
+        .unwrap_or_default();
 
     let config = Config::new(&inputs)
         .watch(should_watch)
         .output(output)
         .pretty_print(pretty_print)
-        .gas(gas);
+        .format(format)
+        .gas(gas)
+        .sizes(sizes)
+        .fail_on_size(fail_on_size)
+        .proxies(proxies)
+        .libraries(libraries);
 
     if config.watch {
         watch(&config).unwrap();
-    } else {
-        build_to_stderr(&config, false);
+    } else if !build_to_stderr(&config, false) {
+        std::process::exit(1);
     }
 
     Ok(())
@@ -78,3 +152,13 @@ fn filter_paths(paths: Vec<impl AsRef<Path>>) -> Vec<impl AsRef<Path>> {
 
     valid
 }
+
+fn parse_proxy(proxy: &str) -> std::result::Result<(String, String), String> {
+    let mut names = proxy.split(':');
+    match (names.next(), names.next(), names.next()) {
+        (Some(proxy), Some(implementation), None) if !proxy.is_empty() && !implementation.is_empty() => {
+            Ok((proxy.to_owned(), implementation.to_owned()))
+        },
+        _ => Err(format!("Expected PROXY:IMPLEMENTATION, got {}", proxy)),
+    }
+}
@@ -0,0 +1,223 @@
+//! CBOR encoded metadata appended by solc to the runtime bytecode, followed by
+//! its length in two bytes. It holds the hash of the metadata JSON, which
+//! changes with any comment or path of the sources, and the version of solc.
+
+use rustc_hex::{FromHex, ToHex};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub hash: Option<SourceHash>,
+    /// `0.6.2` for releases, the whole version for nightly builds
+    pub solc: Option<String>,
+    pub experimental: bool,
+}
+
+/// Hash of the metadata JSON, to retrieve it from IPFS or Swarm
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceHash {
+    /// Multihash, displayed as a CIDv0, e.g. `Qm...`
+    Ipfs(Vec<u8>),
+    Bzzr0(Vec<u8>),
+    Bzzr1(Vec<u8>),
+}
+
+impl fmt::Display for SourceHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceHash::Ipfs(hash) => write!(f, "ipfs://{}", base58(hash)),
+            SourceHash::Bzzr0(hash) => write!(f, "bzzr0://{}", hash.to_hex::<String>()),
+            SourceHash::Bzzr1(hash) => write!(f, "bzzr1://{}", hash.to_hex::<String>()),
+        }
+    }
+}
+
+/// Splits the hex encoded bytecode into the code and the metadata, including
+/// its length, when it ends with valid metadata
+pub fn split(bin: &str) -> Option<(&str, &str)> {
+    let length = bin.len().checked_sub(4).and_then(|i| bin.get(i..))?;
+    let length = usize::from_str_radix(length, 16).ok()?;
+    let start = bin.len().checked_sub(4 + length * 2)?;
+    decode(bin.get(start..bin.len() - 4)?)?;
+    Some((&bin[..start], &bin[start..]))
+}
+
+/// Metadata at the end of the hex encoded bytecode
+pub fn from_bytecode(bin: &str) -> Option<Metadata> {
+    let (_, metadata) = split(bin)?;
+    decode(&metadata[..metadata.len() - 4])
+}
+
+/// Decodes the hex encoded CBOR map, without the length
+fn decode(cbor: &str) -> Option<Metadata> {
+    let bytes: Vec<u8> = cbor.from_hex().ok()?;
+    let mut reader = Reader { data: &bytes, position: 0 };
+    let entries = match reader.value(0)? {
+        Value::Map(entries) if reader.position == bytes.len() => entries,
+        _ => return None,
+    };
+
+    let mut metadata = Metadata {
+        hash: None,
+        solc: None,
+        experimental: false,
+    };
+    for (key, value) in entries.into_iter() {
+        match (key.as_str(), value) {
+            ("ipfs", Value::Bytes(hash)) => metadata.hash = Some(SourceHash::Ipfs(hash)),
+            ("bzzr0", Value::Bytes(hash)) => metadata.hash = Some(SourceHash::Bzzr0(hash)),
+            ("bzzr1", Value::Bytes(hash)) => metadata.hash = Some(SourceHash::Bzzr1(hash)),
+            ("solc", Value::Bytes(version)) if version.len() == 3 => {
+                metadata.solc = Some(format!("{}.{}.{}", version[0], version[1], version[2]));
+            },
+            ("solc", Value::Text(version)) => metadata.solc = Some(version),
+            ("experimental", Value::Bool(experimental)) => metadata.experimental = experimental,
+            _ => {},
+        }
+    }
+    Some(metadata)
+}
+
+/// Nesting of arrays and maps past which the metadata is rejected, solc
+/// doesn't nest them at all
+const MAX_DEPTH: usize = 16;
+
+/// The subset of CBOR used by solc
+enum Value {
+    Bytes(Vec<u8>),
+    Text(String),
+    Bool(bool),
+    Map(Vec<(String, Value)>),
+    /// Integers and arrays, not used by the known keys
+    Other,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn value(&mut self, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return match info {
+                20 => Some(Value::Bool(false)),
+                21 => Some(Value::Bool(true)),
+                _ => None,
+            };
+        }
+
+        let argument = match info {
+            0..=23 => u64::from(info),
+            24 => self.uint(1)?,
+            25 => self.uint(2)?,
+            26 => self.uint(4)?,
+            27 => self.uint(8)?,
+            _ => return None,
+        };
+        match major {
+            0 => Some(Value::Other),
+            2 => Some(Value::Bytes(self.take(argument as usize)?.to_vec())),
+            3 => String::from_utf8(self.take(argument as usize)?.to_vec()).ok().map(Value::Text),
+            4 => (0..argument).map(|_| self.value(depth + 1)).collect::<Option<Vec<Value>>>().map(|_| Value::Other),
+            5 => (0..argument)
+                .map(|_| match self.value(depth + 1)? {
+                    Value::Text(key) => Some((key, self.value(depth + 1)?)),
+                    _ => None,
+                })
+                .collect::<Option<Vec<(String, Value)>>>()
+                .map(Value::Map),
+            _ => None,
+        }
+    }
+
+    fn uint(&mut self, size: usize) -> Option<u64> {
+        Some(self.take(size)?.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+
+    fn take(&mut self, size: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position.checked_add(size)?)?;
+        self.position += size;
+        Some(bytes)
+    }
+}
+
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in bytes.iter() {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    "1".repeat(zeros) + &digits.iter().rev().map(|&d| ALPHABET[d as usize] as char).collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO_WORLD: &str = "6080604052348015600f57600080fd5b50600436106028576000357c0100000000000000000000000000000000000000000000000000000000900480\
+        56fea2646970667358221220ec52c46cd904fdc3f6ffdb72721846239a5bb061487afb8d1ba689f6b12b664564736f6c63430006020033";
+
+    #[test]
+    fn test_ipfs_metadata() {
+        let (code, metadata) = split(HELLO_WORLD).unwrap();
+        assert!(code.ends_with("56fe"));
+        assert!(metadata.starts_with("a264"));
+        assert!(metadata.ends_with("0033"));
+
+        let metadata = from_bytecode(HELLO_WORLD).unwrap();
+        assert_eq!(metadata.solc.as_deref(), Some("0.6.2"));
+        assert!(!metadata.experimental);
+        assert_eq!(
+            metadata.hash.unwrap().to_string(),
+            "ipfs://QmeFA8GB1yehR2J2RwAQpKqvZ4TtZBK5ej2EKw4XkK1Ktp"
+        );
+    }
+
+    #[test]
+    fn test_bzzr_metadata() {
+        // solc 0.5.11, with the experimental ABI encoder
+        let bin = "6080fe\
+            a365627a7a72315820d0abf5a1a1ad81d8b5e8a9ae3d72bd5c0da8d0e8f5fcbf08779fe2a0d3a3e0b16c6578706572696d656e74616cf564736f6c634300050b0040";
+        let metadata = from_bytecode(bin).unwrap();
+        assert_eq!(
+            metadata.hash,
+            Some(SourceHash::Bzzr1("d0abf5a1a1ad81d8b5e8a9ae3d72bd5c0da8d0e8f5fcbf08779fe2a0d3a3e0b1".from_hex().unwrap()))
+        );
+        assert_eq!(metadata.solc.as_deref(), Some("0.5.11"));
+        assert!(metadata.experimental);
+    }
+
+    #[test]
+    fn test_without_metadata() {
+        assert_eq!(split("6080604052"), None);
+        assert_eq!(split(""), None);
+        assert_eq!(from_bytecode("60806040520033"), None);
+    }
+
+    #[test]
+    fn test_nesting() {
+        // {"a": [[...[0]...]]}
+        let nested = |depth: usize| format!("a16161{}00", "81".repeat(depth));
+        assert!(decode(&nested(MAX_DEPTH - 1)).is_some());
+        assert!(decode(&nested(MAX_DEPTH)).is_none());
+        assert!(decode(&nested(100_000)).is_none());
+    }
+}
@@ -0,0 +1,475 @@
+//! Truffle-like migrations: numbered files of steps deploying contracts and
+//! sending transactions to them. The number of the last migration completed
+//! is kept on chain by the `Migrations` contract.
+
+use crate::abi::{self, Abi, Token};
+use crate::contract::{Address, Contract};
+use crate::deploy::Deployer;
+use crate::rpc::{Client, TransactionRequest};
+use ethereum_types::{H256, U256};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+/// Name of the contract keeping track of the migrations
+pub const MIGRATIONS: &str = "Migrations";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// Number the file name starts with, e.g. `2` for `2_deploy_token.toml`
+    pub number: u64,
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Step {
+    Deploy(DeployStep),
+    Send(SendStep),
+}
+
+/// Deploys the contract of the artifact `deploy`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeployStep {
+    pub deploy: String,
+    /// Arguments of the constructor, `$Name` is the address of the contract `Name`
+    #[serde(default)]
+    pub args: Vec<Value>,
+    /// Wei sent to the constructor
+    pub value: Option<Value>,
+}
+
+/// Sends a transaction calling `function` of the deployed contract `send`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SendStep {
+    pub send: String,
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<Value>,
+    pub value: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MigrationFile {
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// `0` for the deployment of `Migrations` itself
+    pub number: u64,
+    pub name: String,
+    pub deployed: Vec<(String, Address)>,
+    pub transactions: Vec<H256>,
+}
+
+pub struct Migrator<'a> {
+    client: &'a Client,
+    deployer: Deployer<'a>,
+    artifacts: PathBuf,
+    dry_run: bool,
+    net_version: String,
+    deployed: HashMap<String, Address>,
+}
+
+impl Migration {
+    /// Reads the `.toml` and `.json` files of the directory, sorted by number
+    pub fn read_dir(dir: impl AsRef<Path>) -> io::Result<Vec<Self>> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+        let mut migrations = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if extension != "toml" && extension != "json" {
+                continue;
+            }
+
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            let (number, name) = stem.split_once('_').unwrap_or((stem, ""));
+            let number = number.parse().map_err(|_| invalid_data(format!(
+                "{}: the name of a migration must start with its number, e.g. 1_initial.toml", path.display()
+            )))?;
+
+            let content = fs::read_to_string(&path)?;
+            let file: MigrationFile = match extension {
+                "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+                _ => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            }.map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?;
+
+            migrations.push(Migration { number, name: name.to_owned(), steps: file.steps });
+        }
+
+        migrations.sort_by_key(|m| m.number);
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].number == pair[1].number) {
+            return Err(invalid_data(format!("Two migrations are numbered {}", pair[0].number)));
+        }
+        Ok(migrations)
+    }
+}
+
+impl<'a> Migrator<'a> {
+    /// Sends the transactions with `deployer`, the artifacts are read from and
+    /// written to `artifacts`
+    pub fn new(client: &'a Client, deployer: Deployer<'a>, artifacts: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            deployer,
+            artifacts: artifacts.into(),
+            dry_run: false,
+            net_version: String::new(),
+            deployed: HashMap::new(),
+        }
+    }
+
+    /// Runs on a snapshot of the chain that is reverted at the end, without
+    /// writing the artifacts. Needs a development node
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Runs the migrations numbered after the last one completed, deploying
+    /// `Migrations` first if it isn't on the network
+    pub fn run(mut self, migrations: &[Migration]) -> io::Result<Vec<MigrationReport>> {
+        self.net_version = self.client.net_version()?;
+        if !self.dry_run {
+            return self.migrate(migrations);
+        }
+
+        let snapshot = self.client.snapshot()
+            .map_err(|e| io::Error::new(e.kind(), format!("Dry runs need a development node: {}", e)))?;
+        let result = self.migrate(migrations);
+        self.client.revert(snapshot)?;
+        result
+    }
+
+    fn migrate(&mut self, migrations: &[Migration]) -> io::Result<Vec<MigrationReport>> {
+        let mut reports = Vec::new();
+        let mut registry = self.read_artifact(MIGRATIONS)?;
+        let registry_address = match registry.get_address(&self.net_version) {
+            Some(address) => address,
+            None => {
+                self.configure(U256::zero(), HashMap::new());
+                let deployment = self.deployer.deploy(&mut registry, &[])?;
+                self.write_artifact(&registry)?;
+                let address = deployment.network.address();
+                reports.push(MigrationReport {
+                    number: 0,
+                    name: MIGRATIONS.to_owned(),
+                    deployed: vec![(MIGRATIONS.to_owned(), address)],
+                    transactions: vec![deployment.receipt.transaction_hash],
+                });
+                address
+            },
+        };
+        self.deployed.insert(MIGRATIONS.to_owned(), registry_address);
+
+        let last = self.last_completed(&registry, registry_address)?;
+        for migration in migrations.iter().filter(|m| m.number > last) {
+            let mut report = MigrationReport {
+                number: migration.number,
+                name: migration.name.clone(),
+                deployed: Vec::new(),
+                transactions: Vec::new(),
+            };
+            for step in migration.steps.iter() {
+                self.execute(step, &mut report)
+                    .map_err(|e| io::Error::new(e.kind(), format!("Migration {}: {}", migration.number, e)))?;
+            }
+
+            let set_completed = function_call(&registry, "setCompleted", &[Token::Uint(migration.number.into())])?;
+            self.configure(U256::zero(), HashMap::new());
+            report.transactions.push(self.deployer.transact(registry_address, set_completed)?.transaction_hash);
+            if self.last_completed(&registry, registry_address)? != migration.number {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!(
+                    "Migration {} wasn't recorded, is 0x{:x} the owner of {}?",
+                    migration.number, self.deployer.sender()?, MIGRATIONS
+                )));
+            }
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+
+    fn execute(&mut self, step: &Step, report: &mut MigrationReport) -> io::Result<()> {
+        match step {
+            Step::Deploy(step) => {
+                let mut contract = self.read_artifact(&step.deploy)?;
+                let inputs = contract.abi.iter().find_map(|abi| match abi {
+                    Abi::Constructor(constructor) => Some(constructor.inputs.clone()),
+                    _ => None,
+                }).unwrap_or_default();
+                let args = abi::tokenize(&inputs, &self.resolve_args(&step.args)?)
+                    .map_err(|e| invalid_input(format!("Invalid arguments of the constructor of {}: {}", step.deploy, e)))?;
+
+                let mut libraries = HashMap::new();
+                for reference in contract.link_references.iter() {
+                    let name = reference.name.rsplit(':').next().unwrap_or(&reference.name);
+                    libraries.insert(name.to_owned(), self.address_of(name)?);
+                }
+
+                self.configure(self.resolve_value(&step.value)?, libraries);
+                let deployment = self.deployer.deploy(&mut contract, &args)?;
+                self.write_artifact(&contract)?;
+
+                let address = deployment.network.address();
+                self.deployed.insert(step.deploy.clone(), address);
+                report.deployed.push((step.deploy.clone(), address));
+                report.transactions.push(deployment.receipt.transaction_hash);
+            },
+            Step::Send(step) => {
+                let contract = self.read_artifact(&step.send)?;
+                let address = self.address_of(&step.send)?;
+                let values = self.resolve_args(&step.args)?;
+                let function = contract.find_function(&step.function, values.len())
+                    .map_err(|e| invalid_input(e.to_string()))?;
+                let calldata = abi::tokenize(&function.inputs, &values)
+                    .and_then(|tokens| function.encode_input(&tokens))
+                    .map_err(|e| invalid_input(format!("Invalid arguments of {}: {}", step.function, e)))?;
+
+                self.configure(self.resolve_value(&step.value)?, HashMap::new());
+                report.transactions.push(self.deployer.transact(address, calldata)?.transaction_hash);
+            },
+        }
+        Ok(())
+    }
+
+    fn last_completed(&self, registry: &Contract, address: Address) -> io::Result<u64> {
+        let function = registry.find_function("last_completed_migration", 0)
+            .map_err(|e| invalid_data(e.to_string()))?;
+        let output = self.client.call(&TransactionRequest {
+            to: Some(address),
+            data: function.encode_input(&[]).map_err(|e| invalid_data(e.to_string()))?,
+            ..TransactionRequest::default()
+        })?;
+        match function.decode_output(&output).map(|mut tokens| tokens.pop()) {
+            Ok(Some(Token::Uint(last))) if last <= U256::from(u64::MAX) => Ok(last.as_u64()),
+            _ => Err(invalid_data(format!("Unexpected last_completed_migration of {} at 0x{:x}", MIGRATIONS, address))),
+        }
+    }
+
+    /// Value and libraries of the next transaction
+    fn configure(&mut self, value: U256, libraries: HashMap<String, Address>) {
+        let deployer = mem::replace(&mut self.deployer, Deployer::new(self.client));
+        self.deployer = deployer.value(value).libraries(libraries);
+    }
+
+    /// Address of the contract deployed by a previous step or recorded in its artifact
+    fn address_of(&self, name: &str) -> io::Result<Address> {
+        if let Some(address) = self.deployed.get(name) {
+            return Ok(*address);
+        }
+        Contract::read_from_dir(&self.artifacts, name).ok()
+            .and_then(|contract| contract.get_address(&self.net_version))
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} isn't deployed on network {}", name, self.net_version),
+            ))
+    }
+
+    fn resolve_args(&self, args: &[Value]) -> io::Result<Vec<String>> {
+        args.iter().map(|arg| self.resolve_arg(arg)).collect()
+    }
+
+    fn resolve_arg(&self, arg: &Value) -> io::Result<String> {
+        match arg {
+            Value::String(s) => match s.strip_prefix('$') {
+                Some(name) => Ok(format!("0x{:x}", self.address_of(name)?)),
+                None => Ok(s.clone()),
+            },
+            Value::Array(values) => Ok(format!("[{}]", self.resolve_args(values)?.join(","))),
+            value => Ok(value.to_string()),
+        }
+    }
+
+    fn resolve_value(&self, value: &Option<Value>) -> io::Result<U256> {
+        let value = match value {
+            Some(value) => self.resolve_arg(value)?,
+            None => return Ok(U256::zero()),
+        };
+        match value.strip_prefix("0x") {
+            Some(hex) => hex.parse().ok(),
+            None => U256::from_dec_str(&value).ok(),
+        }.ok_or_else(|| invalid_input(format!("Invalid value: {}", value)))
+    }
+
+    fn read_artifact(&self, name: &str) -> io::Result<Contract> {
+        Contract::read_from_dir(&self.artifacts, name)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}.json in {}: {}", name, self.artifacts.display(), e)))
+    }
+
+    /// Rewrites the artifact in the format and layout it has, unless it's a dry run
+    fn write_artifact(&self, contract: &Contract) -> io::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        contract.update_file(self.artifacts.join(format!("{}.json", contract.name)))
+    }
+}
+
+fn function_call(contract: &Contract, name: &str, tokens: &[Token]) -> io::Result<Vec<u8>> {
+    contract.find_function(name, tokens.len())
+        .and_then(|function| function.encode_input(tokens))
+        .map_err(|e| invalid_data(format!("{}: {}", contract.name, e)))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+    use crate::rpc::mock::MockNode;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    const ADDRESS: &str = "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab";
+
+    /// Node whose `Migrations` stores what `setCompleted` is sent
+    fn node() -> MockNode {
+        let last = Arc::new(Mutex::new(0u64));
+        MockNode::start(move |method, params| Ok(match method {
+            "net_version" => json!("5777"),
+            "eth_accounts" => json!(["0x0000000000000000000000000000000000000001"]),
+            "evm_snapshot" => json!("0x1"),
+            "evm_revert" => json!(true),
+            "eth_call" => json!(format!("0x{:064x}", *last.lock().unwrap())),
+            "eth_sendTransaction" => {
+                let data = params[0]["data"].as_str().unwrap();
+                if let Some(completed) = data.strip_prefix("0xfdacd576") {
+                    *last.lock().unwrap() = u64::from_str_radix(completed, 16).unwrap();
+                }
+                json!(format!("0x{:064x}", data.len()))
+            },
+            "eth_getTransactionReceipt" => json!({
+                "transactionHash": params[0],
+                "blockNumber": "0x2a",
+                "contractAddress": ADDRESS,
+                "status": "0x1"
+            }),
+            "eth_getBlockByNumber" => json!({ "timestamp": "0x5f5e1000" }),
+            "eth_getCode" => json!("0x6080"),
+            _ => return Err(json!({ "code": -32601, "message": "method not found" })),
+        }))
+    }
+
+    fn artifacts() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let migrations = human::parse_all("
+            function last_completed_migration() view returns (uint256)
+            function setCompleted(uint256 completed)
+        ").unwrap();
+        Contract::new(MIGRATIONS.to_owned(), migrations, "6080".to_owned()).write_to_dir(dir.path(), true).unwrap();
+        let token = human::parse_all("
+            constructor(uint256 supply)
+            function mint(address to, uint256 amount)
+        ").unwrap();
+        Contract::new("Token".to_owned(), token, "6080".to_owned()).write_to_dir(dir.path(), false).unwrap();
+        dir
+    }
+
+    fn migrations() -> Vec<Migration> {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1_token.toml"), r#"
+            [[steps]]
+            deploy = "Token"
+            args = [1000]
+        "#).unwrap();
+        fs::write(dir.path().join("2_mint.json"), r#"{
+            "steps": [{ "send": "Token", "function": "mint", "args": ["$Token", "5"] }]
+        }"#).unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        Migration::read_dir(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let migrations = migrations();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!((migrations[0].number, migrations[0].name.as_str()), (1, "token"));
+        assert_eq!(migrations[0].steps, vec![Step::Deploy(DeployStep {
+            deploy: "Token".to_owned(),
+            args: vec![json!(1000)],
+            value: None,
+        })]);
+        assert_eq!(migrations[1].steps, vec![Step::Send(SendStep {
+            send: "Token".to_owned(),
+            function: "mint".to_owned(),
+            args: vec![json!("$Token"), json!("5")],
+            value: None,
+        })]);
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1_a.toml"), "").unwrap();
+        fs::write(dir.path().join("1_b.json"), "{}").unwrap();
+        assert!(Migration::read_dir(dir.path()).is_err());
+
+        fs::write(dir.path().join("initial.toml"), "").unwrap();
+        assert!(Migration::read_dir(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let node = node();
+        let client = Client::new(&node.url()).unwrap();
+        let artifacts = artifacts();
+
+        let reports = Migrator::new(&client, Deployer::new(&client), artifacts.path()).run(&migrations()).unwrap();
+        let numbers = reports.iter().map(|r| r.number).collect::<Vec<u64>>();
+        assert_eq!(numbers, vec![0, 1, 2]);
+        assert_eq!(reports[1].deployed, vec![("Token".to_owned(), ADDRESS[2..].parse().unwrap())]);
+        assert_eq!(reports[2].transactions.len(), 2);
+
+        let token = Contract::read_from_dir(artifacts.path(), "Token").unwrap();
+        assert_eq!(token.get_address("5777"), Some(ADDRESS[2..].parse().unwrap()));
+        let registry = Contract::read_from_dir(artifacts.path(), MIGRATIONS).unwrap();
+        assert!(registry.get_address("5777").is_some());
+
+        let mint = node.requests().into_iter()
+            .filter(|(m, _)| m == "eth_sendTransaction")
+            .map(|(_, params)| params[0]["data"].as_str().unwrap().to_owned())
+            .find(|data| data.starts_with("0x40c10f19"))
+            .unwrap();
+        assert!(mint.contains(&ADDRESS[2..]));
+
+        let reports = Migrator::new(&client, Deployer::new(&client), artifacts.path()).run(&migrations()).unwrap();
+        assert!(reports.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let node = node();
+        let client = Client::new(&node.url()).unwrap();
+        let artifacts = artifacts();
+
+        let reports = Migrator::new(&client, Deployer::new(&client), artifacts.path())
+            .dry_run(true)
+            .run(&migrations())
+            .unwrap();
+        assert_eq!(reports.len(), 3);
+
+        let methods = node.requests().into_iter().map(|(m, _)| m).collect::<Vec<String>>();
+        assert_eq!(methods.iter().filter(|m| m.starts_with("evm_")).collect::<Vec<_>>(), vec!["evm_snapshot", "evm_revert"]);
+        assert!(Contract::read_from_dir(artifacts.path(), "Token").unwrap().networks.is_empty());
+        assert!(Contract::read_from_dir(artifacts.path(), MIGRATIONS).unwrap().networks.is_empty());
+    }
+}
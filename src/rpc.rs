@@ -0,0 +1,333 @@
+//! Minimal JSON-RPC client of Ethereum nodes over plain HTTP, enough to deploy
+//! and call contracts on a local or development node.
+
+use crate::contract::Address;
+use ethereum_types::{H256, U256, U64};
+use rustc_hex::{FromHex, ToHex};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+pub(crate) mod mock;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Client {
+    /// `host:port`
+    host: String,
+    path: String,
+    id: Cell<u64>,
+    /// Interval between the polls of `wait_for_receipt`
+    pub poll_interval: Duration,
+}
+
+/// Parameters of `eth_sendTransaction`, `eth_call` and `eth_estimateGas`
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Address>,
+    /// `None` to create a contract
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    #[serde(serialize_with = "serialize_bytes")]
+    pub data: Vec<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    pub transaction_hash: H256,
+    pub block_number: U64,
+    #[serde(default)]
+    pub from: Option<Address>,
+    #[serde(default)]
+    pub contract_address: Option<Address>,
+    #[serde(default)]
+    pub gas_used: Option<U256>,
+    /// `1` on success, missing before Byzantium
+    #[serde(default)]
+    pub status: Option<U64>,
+    #[serde(default)]
+    pub logs: Vec<ReceiptLog>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReceiptLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    #[serde(deserialize_with = "deserialize_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Receipt {
+    pub fn succeeded(&self) -> bool {
+        !matches!(self.status, Some(status) if status.is_zero())
+    }
+}
+
+impl Client {
+    /// Only `http://` URLs are supported
+    pub fn new(url: &str) -> io::Result<Self> {
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| invalid_input(format!("Only http:// URLs are supported, got {}", url)))?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let host = if host.contains(':') { host.to_owned() } else { format!("{}:80", host) };
+
+        Ok(Self {
+            host,
+            path: path.to_owned(),
+            id: Cell::new(0),
+            poll_interval: Duration::from_millis(500),
+        })
+    }
+
+    /// Calls `method`, the error of the node is returned as `io::ErrorKind::Other`
+    pub fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> io::Result<T> {
+        self.id.set(self.id.get() + 1);
+        let body = json!({ "jsonrpc": "2.0", "id": self.id.get(), "method": method, "params": params }).to_string();
+
+        let mut stream = TcpStream::connect(&self.host)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path, self.host, body.len(), body
+        )?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let response: Value = serde_json::from_slice(&http_body(&response)?)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(io::Error::other(format!(
+                "{} failed: {} ({})",
+                method,
+                error["message"].as_str().unwrap_or("unknown error"),
+                error["code"]
+            )));
+        }
+        serde_json::from_value(response["result"].clone()).map_err(|e| invalid_data(format!("{}: {}", method, e)))
+    }
+
+    pub fn net_version(&self) -> io::Result<String> {
+        self.request("net_version", json!([]))
+    }
+
+    pub fn chain_id(&self) -> io::Result<u64> {
+        self.request::<U64>("eth_chainId", json!([])).map(|id| id.as_u64())
+    }
+
+    /// Unlocked accounts of the node
+    pub fn accounts(&self) -> io::Result<Vec<Address>> {
+        self.request("eth_accounts", json!([]))
+    }
+
+    /// Nonce of the next transaction of `address`, pending ones included
+    pub fn transaction_count(&self, address: Address) -> io::Result<U256> {
+        self.request("eth_getTransactionCount", json!([address, "pending"]))
+    }
+
+    pub fn gas_price(&self) -> io::Result<U256> {
+        self.request("eth_gasPrice", json!([]))
+    }
+
+    pub fn estimate_gas(&self, transaction: &TransactionRequest) -> io::Result<U256> {
+        self.request("eth_estimateGas", json!([transaction]))
+    }
+
+    /// Output of a call that isn't mined, at the latest block
+    pub fn call(&self, transaction: &TransactionRequest) -> io::Result<Vec<u8>> {
+        let output: String = self.request("eth_call", json!([transaction, "latest"]))?;
+        decode_hex(&output)
+    }
+
+    /// Sends a transaction signed by the node, `from` must be unlocked
+    pub fn send_transaction(&self, transaction: &TransactionRequest) -> io::Result<H256> {
+        self.request("eth_sendTransaction", json!([transaction]))
+    }
+
+    pub fn send_raw_transaction(&self, transaction: &[u8]) -> io::Result<H256> {
+        self.request("eth_sendRawTransaction", json!([format!("0x{}", transaction.to_hex::<String>())]))
+    }
+
+    pub fn transaction_receipt(&self, hash: H256) -> io::Result<Option<Receipt>> {
+        self.request("eth_getTransactionReceipt", json!([hash]))
+    }
+
+    /// Polls the receipt until the transaction is mined
+    pub fn wait_for_receipt(&self, hash: H256, timeout: Duration) -> io::Result<Receipt> {
+        let start = Instant::now();
+        loop {
+            if let Some(receipt) = self.transaction_receipt(hash)? {
+                return Ok(receipt);
+            }
+            if start.elapsed() > timeout {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, format!("0x{:x} wasn't mined in {:?}", hash, timeout)));
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+
+    /// Unix time of the block
+    pub fn block_timestamp(&self, number: U64) -> io::Result<u64> {
+        let block: Value = self.request("eth_getBlockByNumber", json!([number, false]))?;
+        serde_json::from_value::<U64>(block["timestamp"].clone())
+            .map(|timestamp| timestamp.as_u64())
+            .map_err(|_| invalid_data(format!("No timestamp in block {}", number)))
+    }
+
+    /// Runtime code at `address`
+    pub fn code(&self, address: Address) -> io::Result<Vec<u8>> {
+        let code: String = self.request("eth_getCode", json!([address, "latest"]))?;
+        decode_hex(&code)
+    }
+
+    /// Snapshot of the chain of a development node (ganache, hardhat, anvil)
+    pub fn snapshot(&self) -> io::Result<U256> {
+        self.request("evm_snapshot", json!([]))
+    }
+
+    /// Reverts the chain to the snapshot, which can't be used again
+    pub fn revert(&self, snapshot: U256) -> io::Result<()> {
+        match self.request("evm_revert", json!([snapshot]))? {
+            true => Ok(()),
+            false => Err(invalid_input(format!("Unknown snapshot {}", snapshot))),
+        }
+    }
+}
+
+/// Body of the HTTP response, fails on status codes other than 2xx
+fn http_body(response: &[u8]) -> io::Result<Vec<u8>> {
+    let end = response.windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid_data("Incomplete HTTP response".to_owned()))?;
+    let head = String::from_utf8_lossy(&response[..end]);
+    let body = &response[end + 4..];
+
+    let status = head.lines().next().unwrap_or_default();
+    if !matches!(status.split_whitespace().nth(1), Some(code) if code.starts_with('2')) {
+        return Err(io::Error::other(format!("{}: {}", status, String::from_utf8_lossy(body))));
+    }
+
+    let chunked = head.lines().any(|l| {
+        let l = l.to_ascii_lowercase();
+        l.starts_with("transfer-encoding:") && l.contains("chunked")
+    });
+    if !chunked {
+        return Ok(body.to_vec());
+    }
+
+    let mut decoded = Vec::new();
+    let mut rest = body;
+    loop {
+        let line_end = rest.windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| invalid_data("Invalid chunked body".to_owned()))?;
+        let size = String::from_utf8_lossy(&rest[..line_end]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+            .map_err(|_| invalid_data("Invalid chunk size".to_owned()))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = rest.get(line_end + 2..line_end + 2 + size)
+            .ok_or_else(|| invalid_data("Truncated chunk".to_owned()))?;
+        decoded.extend_from_slice(chunk);
+        rest = rest.get(line_end + 4 + size..).unwrap_or_default();
+    }
+}
+
+pub(crate) fn decode_hex(hex: &str) -> io::Result<Vec<u8>> {
+    hex.trim_start_matches("0x").from_hex().map_err(|e| invalid_data(e.to_string()))
+}
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", bytes.to_hex::<String>()))
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    decode_hex(&hex).map_err(serde::de::Error::custom)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::mock::MockNode;
+
+    #[test]
+    fn test_request() {
+        let node = MockNode::start(|method, params| match method {
+            "net_version" => Ok(json!("5777")),
+            "eth_getCode" => {
+                assert_eq!(params[1], "latest");
+                Ok(json!("0x6080"))
+            },
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        });
+        let client = Client::new(&node.url()).unwrap();
+
+        assert_eq!(client.net_version().unwrap(), "5777");
+        assert_eq!(client.code(Address::zero()).unwrap(), vec![0x60, 0x80]);
+
+        let err = client.accounts().unwrap_err();
+        assert_eq!(err.to_string(), "eth_accounts failed: method not found (-32601)");
+        assert_eq!(node.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_url() {
+        assert!(Client::new("https://mainnet.example").is_err());
+        let client = Client::new("http://127.0.0.1:8545").unwrap();
+        assert_eq!((client.host.as_str(), client.path.as_str()), ("127.0.0.1:8545", "/"));
+        let client = Client::new("http://node.local/rpc").unwrap();
+        assert_eq!((client.host.as_str(), client.path.as_str()), ("node.local:80", "/rpc"));
+    }
+
+    #[test]
+    fn test_http_body() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        assert_eq!(http_body(response).unwrap(), b"{\"a\":1}");
+
+        let response = b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n";
+        assert!(http_body(response).is_err());
+    }
+
+    #[test]
+    fn test_transaction_request() {
+        let request = TransactionRequest {
+            from: Some(Address::zero()),
+            gas: Some(U256::from(21000)),
+            data: vec![0x60, 0x80],
+            ..TransactionRequest::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({ "from": "0x0000000000000000000000000000000000000000", "gas": "0x5208", "data": "0x6080" })
+        );
+    }
+}
@@ -1,7 +1,6 @@
 use crate::abi::*;
-use crate::contract::GasEstimates;
+use crate::contract::{ConstructionGas, Gas, GasEstimates};
 use std::collections::HashMap;
-use std::str::Lines;
 use std::iter::Peekable;
 use std::path::Path;
 use std::process::{Command, Stdio};
@@ -11,7 +10,13 @@ pub struct SolcContract {
     pub name: String,
     pub abi: Vec<Abi>,
     pub bin: String,
-    pub gas_estimates: GasEstimates,
+    pub bin_runtime: String,
+    pub gas_estimates: Option<GasEstimates>,
+    /// Fully qualified names of the libraries keyed by their placeholders
+    pub libraries: HashMap<String, String>,
+    pub metadata: Option<String>,
+    pub source_map: Option<String>,
+    pub source_map_runtime: Option<String>,
 }
 
 pub enum CompilerInput<'a> {
@@ -31,15 +36,56 @@ macro_rules! assert_line {
     };
 }
 
+const SECTIONS: &[&str] = &[
+    "Gas estimation:",
+    "Binary:",
+    "Binary of the runtime part:",
+    "Metadata:",
+    "Contract JSON ABI",
+];
+
 pub fn compile_contract(input: CompilerInput) -> Result<Vec<SolcContract>> {
-    Ok(parse_output(&call_compiler(input, &["--abi", "--gas", "--bin"])?))
+    Ok(parse_output(&call_compiler(input, &[
+        "--abi", "--gas", "--bin", "--bin-runtime", "--metadata", "--combined-json", "srcmap,srcmap-runtime",
+    ])?))
+}
+
+/// Version of the solc in the `PATH`, e.g. `0.6.2+commit.bacdbe57.Linux.g++`
+pub fn version() -> Option<String> {
+    let output = Command::new("solc").arg("--version").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|l| l.starts_with("Version:"))
+        .map(|l| l["Version:".len()..].trim().to_owned())
+}
+
+/// Version without the platform, e.g. `0.6.2+commit.bacdbe57`, as recorded
+/// in the metadata
+pub fn release(version: &str) -> &str {
+    let hash = match version.find("+commit.") {
+        Some(i) => i + "+commit.".len(),
+        None => return version,
+    };
+    match version[hash..].find('.') {
+        Some(end) => &version[..hash + end],
+        None => version,
+    }
 }
 
 pub fn parse_output(output: &str) -> Vec<SolcContract> {
     let mut contracts = Vec::new();
     let mut lines = output.lines().peekable();
 
-    // The output sa of solc 0.6.2 is
+    // The source maps come first, as the JSON asked by `--combined-json`
+    while matches!(lines.peek(), Some(l) if l.trim().is_empty()) {
+        lines.next();
+    }
+    let source_maps = match lines.next_if(|l| l.trim_start().starts_with('{')) {
+        Some(json) => parse_source_maps(json),
+        None => HashMap::new(),
+    };
+
+    // Then, for each contract, the output of solc 0.6.2 is
     //
     // _Blank line_
     // ======= contract_path:ContractName =======
@@ -50,33 +96,65 @@ pub fn parse_output(output: &str) -> Vec<SolcContract> {
     //    function_name(args): amount
     // *Binary:*
     // _Output_
+    // _Placeholders of the libraries, as `// $hash$ -> path:Library`_
+    // *Binary of the runtime part:*
+    // _Output_
+    // _Placeholders of the libraries_
+    // *Metadata:*
+    // _Metadata JSON_
     // *Contract JSON ABI*
     // _JSON ABI_
+    //
+    // Sections are looked up by their title, as some of them are left out,
+    // e.g. the gas estimation of interfaces
     loop {
-        // Each iteration parses a contract
-
-        // Skip blank line
-        if lines.next().is_none() {
-            // No more contracts to parse
-            break;
+        // Skip blank lines before the name of the contract
+        while matches!(lines.peek(), Some(l) if l.trim().is_empty()) {
+            lines.next();
         }
+        let (id, name) = match lines.next() {
+            Some(line) => (line.trim_matches(|c| c == ' ' || c == '='), parse_name(line)),
+            // No more contracts to parse
+            None => break,
+        };
+        let (source_map, source_map_runtime) = source_maps.get(id).cloned().unwrap_or_default();
+
+        let mut gas_estimates = None;
+        let mut bin = String::new();
+        let mut bin_runtime = String::new();
+        let mut abi = Vec::new();
+        let mut libraries = HashMap::new();
+        let mut metadata = None;
+        while let Some(title) = lines.next_if(|l| SECTIONS.contains(&l.trim())) {
+            let mut section = Vec::new();
+            while let Some(line) = lines.next_if(|l| !SECTIONS.contains(&l.trim()) && !l.starts_with("=======")) {
+                section.push(line);
+            }
 
-        let name = parse_name(next_line!(lines));
-
-        assert_line!(lines, "Gas estimation:");
-        let gas_estimates = parse_gas_estimates(&mut lines);
-
-        assert_line!(lines, "Binary:");
-        let bin = next_line!(lines);
-
-        assert_line!(lines, "Contract JSON ABI");
-        let abi = next_line!(lines);
+            match title.trim() {
+                "Gas estimation:" => {
+                    gas_estimates = Some(parse_gas_estimates(&mut section.into_iter().peekable()));
+                },
+                "Binary:" => bin = parse_binary(&section, &mut libraries),
+                "Binary of the runtime part:" => bin_runtime = parse_binary(&section, &mut libraries),
+                "Metadata:" => metadata = section.iter().find(|l| !l.trim().is_empty()).map(|l| l.trim().to_owned()),
+                _ => {
+                    let json = section.iter().find(|l| !l.trim().is_empty()).expect("Solc changed the output format");
+                    abi = Abi::from_json_array(json).expect("Couldn't parse solc JSON abi");
+                },
+            }
+        }
 
         contracts.push(SolcContract {
             name,
-            abi: Abi::from_json_array(abi).expect("Couldn't parse solc JSON abi"),
-            bin:bin.to_owned(),
+            abi,
+            bin,
+            bin_runtime,
             gas_estimates,
+            libraries,
+            metadata,
+            source_map,
+            source_map_runtime,
         });
     }
 
@@ -90,54 +168,73 @@ fn parse_name(line: &str) -> String {
         .expect("Solc changed the output format"))
 }
 
-fn parse_gas_estimates(lines: &mut Peekable<Lines>) -> GasEstimates {
+/// Source maps of the creation and runtime code keyed by `path:Name`, empty
+/// ones are left out
+fn parse_source_maps(json: &str) -> HashMap<String, (Option<String>, Option<String>)> {
+    let json: serde_json::Value = serde_json::from_str(json).expect("Solc changed the output format");
+    let source_map = |contract: &serde_json::Value, key: &str| contract[key].as_str()
+        .filter(|map| !map.is_empty())
+        .map(str::to_owned);
+    json["contracts"].as_object()
+        .map(|contracts| contracts.iter()
+            .map(|(id, c)| (id.clone(), (source_map(c, "srcmap"), source_map(c, "srcmap-runtime"))))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// The first line holds the bytecode, it's empty for abstract contracts and
+/// interfaces, followed by the libraries to link
+fn parse_binary(section: &[&str], libraries: &mut HashMap<String, String>) -> String {
+    for line in section.iter().skip(1) {
+        let mut legend = line.trim_start_matches("//").split("->").map(str::trim);
+        if let (Some(hash), Some(library)) = (legend.next(), legend.next()) {
+            libraries.insert(format!("__{}__", hash), library.to_owned());
+        }
+    }
+    section.first().map(|l| l.trim().to_owned()).unwrap_or_default()
+}
+
+fn parse_gas_estimates<'a>(lines: &mut Peekable<impl Iterator<Item = &'a str>>) -> GasEstimates {
     assert_line!(lines, "construction:");
 
+    // e.g. `93 + 79000 = 79093`
     let construction = next_line!(lines)
         .split('=')
-        .last()
+        .next()
         .expect("Solc changed the output format")
-        .trim();
+        .split('+')
+        .map(|gas| gas.parse().expect("Solc changed the output format"))
+        .collect::<Vec<Gas>>();
+    let construction = match construction[..] {
+        [execution, code_deposit] => ConstructionGas { execution, code_deposit },
+        _ => panic!("Solc changed the output format"),
+    };
 
     let mut external = HashMap::new();
     let mut internal = HashMap::new();
 
     let mut current = &mut external;
 
-    loop {
-        {
-            let end_of_estimates = lines.peek()
-                .and_then(|&n| if n.trim() == "Binary:" { None } else { Some("") })
-                .is_none();
-            if end_of_estimates {
-                break;
-            }
-        }
-
-        let line = next_line!(lines).trim();
-        if line == "internal:" {
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        } else if line == "internal:" {
             current = &mut internal;
             continue;
         } else if line == "external:" {
             continue;
         }
 
-        let mut line = line.split(':');
-        let name = line.next()
-            .expect("Solc changed the output format")
-            .split('(')
-            .next()
-            .expect("Solc changed the output format");
-
-        let value = line.next()
-            .expect("Solc changed the output format")
-            .trim();
+        // The signature has no colons, the value comes after the last one
+        let (signature, value) = line.rsplit_once(':').expect("Solc changed the output format");
+        let value = value.parse().expect("Solc changed the output format");
 
-        current.insert(name.to_owned(), value.to_owned());
+        current.insert(signature.trim().to_owned(), value);
     }
 
     GasEstimates {
-        construction: construction.to_owned(),
+        construction,
         external,
         internal
     }
@@ -150,6 +247,19 @@ pub fn call_compiler(contract: CompilerInput, args: &[&str]) -> Result<String> {
     }
 }
 
+/// Output of `solc --standard-json` for the given input
+pub fn compile_standard_json(input: &str) -> Result<String> {
+    let mut solc = Command::new("solc")
+        .arg("--standard-json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    solc.stdin.as_mut().expect("Failed to open stdin for solc").write_all(input.as_bytes())?;
+    let output = solc.wait_with_output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 fn call_compiler_stdin(contract: &str, args: &[&str]) -> Result<String> {
     let mut solc = Command::new("solc");
 
@@ -189,3 +299,106 @@ fn call_compiler_path(path: impl AsRef<Path>, args: &[&str]) -> Result<String> {
 
     Ok(String::from(String::from_utf8_lossy(&solc.stdout)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "{\"contracts\":{\"<stdin>:IToken\":{\"srcmap\":\"\",\"srcmap-runtime\":\"\"},\"<stdin>:Token\":{\"srcmap\":\"25:480:0:-:0;;;\",\"srcmap-runtime\":\"25:480:0:-:0;;;;8:9:-1\"}},\"version\":\"0.6.2+commit.bacdbe57.Linux.g++\"}
+
+======= <stdin>:Token =======
+Gas estimation:
+construction:
+   infinite + 103000 = infinite
+external:
+   balanceOf(address):\t1257
+   transfer(address,uint256):\tinfinite
+   transfer(address,uint256,bytes):\t2000
+internal:
+   _mint(address,uint256):\t42
+Binary:
+6080604052
+Binary of the runtime part:
+60806040
+Metadata:
+{\"compiler\":{\"version\":\"0.6.2+commit.bacdbe57\"},\"language\":\"Solidity\"}
+Contract JSON ABI
+[{\"inputs\":[{\"internalType\":\"address\",\"name\":\"owner\",\"type\":\"address\"}],\"name\":\"balanceOf\",\"outputs\":[],\"stateMutability\":\"view\",\"type\":\"function\"}]
+
+======= <stdin>:Vault =======
+Binary:
+6080__$2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$__00
+// $2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$ -> contracts/Math.sol:Math
+Binary of the runtime part:
+60__$2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$__
+// $2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$ -> contracts/Math.sol:Math
+Contract JSON ABI
+[]
+
+======= <stdin>:IToken =======
+Binary:
+
+Binary of the runtime part:
+
+Contract JSON ABI
+[]
+";
+
+    #[test]
+    fn test_release() {
+        assert_eq!(release("0.6.2+commit.bacdbe57.Linux.g++"), "0.6.2+commit.bacdbe57");
+        assert_eq!(release("0.8.19+commit.7dd6d404.Darwin.appleclang"), "0.8.19+commit.7dd6d404");
+        assert_eq!(release("0.6.2+commit.bacdbe57"), "0.6.2+commit.bacdbe57");
+        assert_eq!(release("0.6.2"), "0.6.2");
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let contracts = parse_output(OUTPUT);
+        assert_eq!(contracts.len(), 3);
+
+        let token = &contracts[0];
+        assert_eq!(token.name, "Token");
+        assert_eq!(token.bin, "6080604052");
+        assert_eq!(token.bin_runtime, "60806040");
+        assert_eq!(token.abi.len(), 1);
+        assert_eq!(token.source_map.as_deref(), Some("25:480:0:-:0;;;"));
+        assert_eq!(token.source_map_runtime.as_deref(), Some("25:480:0:-:0;;;;8:9:-1"));
+        assert_eq!(
+            token.metadata.as_deref(),
+            Some("{\"compiler\":{\"version\":\"0.6.2+commit.bacdbe57\"},\"language\":\"Solidity\"}")
+        );
+
+        let gas = token.gas_estimates.as_ref().unwrap();
+        assert_eq!(gas.construction.execution, Gas::Infinite);
+        assert_eq!(gas.construction.code_deposit, Gas::Finite(103000));
+        assert_eq!(gas.construction.total(), Gas::Infinite);
+        assert_eq!(gas.external.get("balanceOf(address)"), Some(&Gas::Finite(1257)));
+        assert_eq!(gas.external.get("transfer(address,uint256)"), Some(&Gas::Infinite));
+        assert_eq!(gas.external.get("transfer(address,uint256,bytes)"), Some(&Gas::Finite(2000)));
+        assert_eq!(gas.internal.get("_mint(address,uint256)"), Some(&Gas::Finite(42)));
+
+        let vault = &contracts[1];
+        assert_eq!(vault.bin, "6080__$2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$__00");
+        assert_eq!(vault.bin_runtime, "60__$2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$__");
+        assert_eq!(
+            vault.libraries.get("__$2bb4e7b8cab2a0c7d9b0b5c4a5ec7b3b8c$__").map(String::as_str),
+            Some("contracts/Math.sol:Math")
+        );
+
+        let interface = &contracts[2];
+        assert_eq!(interface.name, "IToken");
+        assert_eq!(interface.bin, "");
+        assert_eq!(interface.bin_runtime, "");
+        assert!(interface.gas_estimates.is_none());
+        assert!(interface.abi.is_empty());
+        assert!(interface.metadata.is_none());
+        assert!(interface.source_map.is_none());
+
+        // Without the source maps
+        let (_, output) = OUTPUT.split_once('\n').unwrap();
+        let contracts = parse_output(output);
+        assert_eq!(contracts.len(), 3);
+        assert!(contracts[0].source_map.is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Tests written in Solidity: the functions of a contract whose names start
+//! with `test` run on a fresh `LocalChain` each, after `setUp`, and fail when
+//! they revert.
+
+use crate::abi::{self, Function};
+use crate::contract::Contract;
+use crate::evm::{Execution, ExecutionError, LocalChain};
+
+const SET_UP: &str = "setUp";
+const PREFIX: &str = "test";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub contract: String,
+    /// Signature of the test function, e.g. `testTransfer()`
+    pub test: String,
+    /// Gas of the transaction calling the test, `setUp` excluded
+    pub gas_used: Option<u64>,
+    /// Revert reason of the failed tests
+    pub failure: Option<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Test functions of the contract, in order of declaration. Functions taking
+/// arguments aren't tests
+pub fn tests(contract: &Contract) -> Vec<&Function> {
+    contract.abi.iter()
+        .filter_map(|abi| match abi {
+            abi::Abi::Function(f) if f.name.starts_with(PREFIX) && f.inputs.is_empty() => Some(f),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Runs the tests of every contract having some, sorted by contract. Only the
+/// tests whose name contains `pattern` run when it's given
+pub fn run(contracts: &[Contract], pattern: Option<&str>) -> Vec<TestResult> {
+    let mut suites = contracts.iter()
+        .filter(|c| !c.bin.is_empty())
+        .map(|c| (c, tests(c)))
+        .filter(|(_, tests)| !tests.is_empty())
+        .collect::<Vec<_>>();
+    suites.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+    let mut results = Vec::new();
+    for (contract, tests) in suites {
+        for test in tests.into_iter().filter(|t| match pattern {
+            Some(pattern) => t.name.contains(pattern),
+            None => true,
+        }) {
+            results.push(run_test(contracts, contract, test));
+        }
+    }
+    results
+}
+
+fn run_test(contracts: &[Contract], contract: &Contract, test: &Function) -> TestResult {
+    let outcome = execute(contracts, contract, test);
+    TestResult {
+        contract: contract.name.clone(),
+        test: test.signature().unwrap_or_else(|_| format!("{}()", test.name)),
+        gas_used: match &outcome {
+            Ok(execution) => Some(execution.gas_used),
+            Err((_, error)) => error.gas_used(),
+        },
+        failure: outcome.err().map(|(stage, error)| match stage {
+            Some(stage) => format!("{} failed: {}", stage, reason(contract, &error)),
+            None => reason(contract, &error),
+        }),
+    }
+}
+
+/// Deploys the contract on a chain of its own and calls `setUp` then the
+/// test, the error tells which of the first two failed
+fn execute(contracts: &[Contract], contract: &Contract, test: &Function) -> Result<Execution, (Option<&'static str>, ExecutionError)> {
+    let mut chain = LocalChain::new();
+    let address = deploy(&mut chain, contracts, contract)
+        .map_err(|e| (Some("Deployment"), e))?
+        .address
+        .ok_or_else(|| (Some("Deployment"), ExecutionError::Invalid("No contract was created".to_owned())))?;
+
+    if let Some(set_up) = contract.get_function(&format!("{}()", SET_UP)) {
+        chain.send(address, set_up, &[]).map_err(|e| (Some(SET_UP), e))?;
+    }
+    chain.send(address, test, &[]).map_err(|e| (None, e))
+}
+
+/// Deploys the contract after the libraries it links to
+fn deploy(chain: &mut LocalChain, contracts: &[Contract], contract: &Contract) -> Result<Execution, ExecutionError> {
+    for reference in contract.link_references.iter() {
+        let name = reference.name.rsplit(':').next().unwrap_or(&reference.name);
+        if let Some(library) = contracts.iter().find(|c| c.name == name) {
+            deploy(chain, contracts, library)?;
+        }
+    }
+    chain.deploy(contract, &[])
+}
+
+/// Revert reason, with the custom errors of the contract decoded
+fn reason(contract: &Contract, error: &ExecutionError) -> String {
+    let data = match error.revert_data() {
+        Some(data) if data.len() >= 4 => data,
+        _ => return error.to_string(),
+    };
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&data[..4]);
+    match contract.get_error_by_selector(selector) {
+        Some(custom) => match custom.decode(data) {
+            Ok(tokens) => format!(
+                "Reverted: {}({})",
+                custom.name,
+                tokens.iter().map(abi::format_token).collect::<Vec<String>>().join(", ")
+            ),
+            Err(_) => error.to_string(),
+        },
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::human;
+    use rustc_hex::ToHex;
+
+    /// Contract whose runtime reverts on `revert` and stops otherwise
+    fn contract(name: &str, abi: &str, revert: &str) -> Contract {
+        let abi = human::parse_all(abi).unwrap();
+        let selector = match abi.iter().find_map(|a| match a {
+            abi::Abi::Function(f) if f.name == revert => Some(f.selector().unwrap()),
+            abi::Abi::Error(e) if e.name == revert => Some(e.selector().unwrap()),
+            _ => None,
+        }) {
+            Some(selector) => selector.to_hex(),
+            None => "00000000".to_owned(),
+        };
+        // Reverts with the calldata, so an error named like the function decodes
+        let runtime = format!("60003560e01c63{}14601057005b366000600037366000fd", selector);
+        let len = runtime.len() / 2;
+        let bin = format!("60{:02x}600c60003960{:02x}6000f3{}", len, len, runtime);
+        Contract::new(name.to_owned(), abi, bin)
+    }
+
+    #[test]
+    fn test_run() {
+        let suite = contract("TokenTest", "
+            function setUp()
+            function testTransfer()
+            function testBurn()
+            function testFuzz(uint256 amount)
+            function helper()
+        ", "testBurn");
+        let other = contract("ATest", "function testOwner()", "");
+        let library = contract("Math", "function add(uint256 a, uint256 b) pure returns (uint256)", "");
+
+        assert_eq!(tests(&suite).len(), 2);
+        assert!(tests(&library).is_empty());
+
+        let results = run(&[suite.clone(), other, library], None);
+        let summary = results.iter()
+            .map(|r| (r.contract.as_str(), r.test.as_str(), r.passed()))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            ("ATest", "testOwner()", true),
+            ("TokenTest", "testTransfer()", true),
+            ("TokenTest", "testBurn()", false),
+        ]);
+        assert!(results.iter().all(|r| r.gas_used.unwrap() >= 21_000));
+        let selector = suite.get_function("testBurn()").unwrap().selector().unwrap();
+        assert_eq!(results[2].failure, Some(format!("Reverted with 0x{}", selector.to_hex::<String>())));
+
+        let results = run(&[suite], Some("Transfer"));
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_set_up_and_custom_errors() {
+        let suite = contract("SetUpTest", "function setUp()\nfunction testA()", "setUp");
+        let results = run(&[suite], None);
+        assert_eq!(results[0].failure.as_deref(), Some("setUp failed: Reverted with 0x0a9254e4"));
+
+        // The selector of `testA()` is also the one of the error `testA()`
+        let suite = contract("ErrorTest", "function testA()\nerror testA()", "testA");
+        let results = run(&[suite], None);
+        assert_eq!(results[0].failure.as_deref(), Some("Reverted: testA()"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Standard JSON input reproducing the bytecode of a contract, as submitted to
+//! block explorers to verify its source. It's built from the metadata JSON of
+//! solc, with the sources read back from the disk.
+
+use crate::contract::Contract;
+use crate::metadata;
+use crate::solc;
+use rustc_hex::ToHex;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use tiny_keccak::keccak256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyInput {
+    /// e.g. `0.6.2+commit.bacdbe57`
+    pub compiler_version: String,
+    /// Fully qualified name of the contract, e.g. `contracts/Token.sol:Token`
+    pub contract_name: String,
+    pub input: StandardJsonInput,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandardJsonInput {
+    pub language: String,
+    pub sources: BTreeMap<String, Source>,
+    pub settings: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    pub content: String,
+}
+
+/// Fails when the contract has no metadata or a source changed since it was
+/// compiled
+pub fn verify_input(contract: &Contract) -> io::Result<VerifyInput> {
+    let metadata: Value = contract.raw_metadata.as_ref()
+        .ok_or_else(|| invalid_data(format!("{} has no metadata", contract.name)))
+        .and_then(|m| serde_json::from_str(m).map_err(|e| invalid_data(e.to_string())))?;
+
+    let mut settings = metadata["settings"].as_object()
+        .cloned()
+        .ok_or_else(|| invalid_data("No settings in the metadata".to_owned()))?;
+    let contract_name = settings.remove("compilationTarget")
+        .and_then(|target| {
+            let (source, name) = target.as_object()?.iter().next()?;
+            Some(format!("{}:{}", source, name.as_str()?))
+        })
+        .ok_or_else(|| invalid_data("No compilation target in the metadata".to_owned()))?;
+
+    // The metadata has them as `path:Name`, the input keyed by path then name
+    if let Some(libraries) = settings.remove("libraries") {
+        let mut grouped: Map<String, Value> = Map::new();
+        for (library, address) in libraries.as_object().into_iter().flatten() {
+            let (source, name) = library.rsplit_once(':').unwrap_or(("", library));
+            grouped.entry(source)
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .expect("Inserted as an object")
+                .insert(name.to_owned(), address.clone());
+        }
+        settings.insert("libraries".to_owned(), Value::Object(grouped));
+    }
+    settings.insert("outputSelection".to_owned(), json!({
+        "*": { "*": ["abi", "evm.bytecode.object", "evm.deployedBytecode.object", "metadata"] }
+    }));
+
+    let mut sources = BTreeMap::new();
+    for (path, source) in metadata["sources"].as_object().into_iter().flatten() {
+        let content = match source["content"].as_str() {
+            Some(content) => content.to_owned(),
+            None => fs::read_to_string(path)?,
+        };
+
+        let hash = format!("0x{}", keccak256(content.as_bytes()).to_hex::<String>());
+        if matches!(source["keccak256"].as_str(), Some(h) if h != hash) {
+            return Err(invalid_data(format!("{} changed since {} was compiled", path, contract.name)));
+        }
+        sources.insert(path.clone(), Source { content });
+    }
+
+    Ok(VerifyInput {
+        compiler_version: metadata["compiler"]["version"].as_str().unwrap_or_default().to_owned(),
+        contract_name,
+        input: StandardJsonInput {
+            language: metadata["language"].as_str().unwrap_or("Solidity").to_owned(),
+            sources,
+            settings: Value::Object(settings),
+        },
+    })
+}
+
+/// Recompiles the input with the solc in the `PATH` and compares the runtime
+/// bytecode with the one of the contract, leaving out the metadata
+pub fn verify(contract: &Contract, input: &VerifyInput) -> io::Result<bool> {
+    let json = serde_json::to_string(&input.input)?;
+    let output: Value = serde_json::from_str(&solc::compile_standard_json(&json)?)?;
+
+    let errors = output["errors"].as_array()
+        .into_iter()
+        .flatten()
+        .filter(|e| e["severity"] == "error")
+        .filter_map(|e| e["formattedMessage"].as_str().or_else(|| e["message"].as_str()))
+        .collect::<Vec<&str>>();
+    if !errors.is_empty() {
+        return Err(invalid_data(errors.join("\n")));
+    }
+
+    let (source, name) = input.contract_name.rsplit_once(':').unwrap_or(("", &input.contract_name));
+    let recompiled = output["contracts"][source][name]["evm"]["deployedBytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| invalid_data(format!("solc didn't output {}", input.contract_name)))?;
+
+    Ok(without_metadata(recompiled) == without_metadata(&contract.bin_runtime))
+}
+
+fn without_metadata(bin: &str) -> &str {
+    metadata::split(bin).map_or(bin, |(code, _)| code)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "pragma solidity ^0.6.0;\ncontract Token {}\n";
+
+    fn contract(path: &str, content: &str) -> Contract {
+        let metadata = json!({
+            "compiler": { "version": "0.6.2+commit.bacdbe57" },
+            "language": "Solidity",
+            "output": {},
+            "settings": {
+                "compilationTarget": { path: "Token" },
+                "evmVersion": "istanbul",
+                "libraries": { "contracts/Math.sol:Math": "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab" },
+                "metadata": { "bytecodeHash": "ipfs" },
+                "optimizer": { "enabled": true, "runs": 200 },
+                "remappings": []
+            },
+            "sources": {
+                path: {
+                    "keccak256": format!("0x{}", keccak256(content.as_bytes()).to_hex::<String>()),
+                    "urls": []
+                }
+            },
+            "version": 1
+        });
+        let mut contract = Contract::new("Token".to_owned(), Vec::new(), String::new());
+        contract.raw_metadata = Some(metadata.to_string());
+        contract
+    }
+
+    #[test]
+    fn test_verify_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Token.sol");
+        fs::write(&path, SOURCE).unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let input = verify_input(&contract(&path, SOURCE)).unwrap();
+        assert_eq!(input.compiler_version, "0.6.2+commit.bacdbe57");
+        assert_eq!(input.contract_name, format!("{}:Token", path));
+        assert_eq!(input.input.language, "Solidity");
+        assert_eq!(input.input.sources[&path].content, SOURCE);
+
+        let settings = &input.input.settings;
+        assert!(settings.get("compilationTarget").is_none());
+        assert_eq!(settings["optimizer"], json!({ "enabled": true, "runs": 200 }));
+        assert_eq!(
+            settings["libraries"],
+            json!({ "contracts/Math.sol": { "Math": "0xe78a0f7e598cc8b0bb87894b0f60dd2a88d6a8ab" } })
+        );
+        assert!(settings["outputSelection"]["*"]["*"].is_array());
+    }
+
+    #[test]
+    fn test_changed_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Token.sol");
+        fs::write(&path, SOURCE).unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let err = verify_input(&contract(&path, "contract Token { uint x; }")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("changed since Token was compiled"));
+    }
+
+    #[test]
+    fn test_without_metadata() {
+        let contract = Contract::new("Token".to_owned(), Vec::new(), String::new());
+        assert!(verify_input(&contract).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! Private keys read from the encrypted JSON keystores of geth and most
+//! wallets, and the signing of legacy (EIP-155) transactions with them.
+
+use crate::contract::Address;
+use crate::rpc::{self, TransactionRequest};
+use aes_ctr::Aes128Ctr;
+use aes_ctr::cipher::stream::{NewStreamCipher, SyncStreamCipher};
+use ethereum_types::{H256, U256};
+use rlp::RlpStream;
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+use tiny_keccak::keccak256;
+
+pub struct Wallet {
+    secret: secp256k1::SecretKey,
+    address: Address,
+}
+
+#[derive(Deserialize)]
+struct Keystore {
+    #[serde(alias = "Crypto")]
+    crypto: Crypto,
+}
+
+#[derive(Deserialize)]
+struct Crypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: ScryptParams,
+    mac: String,
+}
+
+#[derive(Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+#[derive(Deserialize)]
+struct ScryptParams {
+    dklen: usize,
+    n: u64,
+    r: u32,
+    p: u32,
+    salt: String,
+}
+
+impl fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Wallet(0x{:x})", self.address)
+    }
+}
+
+impl Wallet {
+    pub fn from_secret(secret: &[u8]) -> io::Result<Self> {
+        let secret = secp256k1::SecretKey::parse_slice(secret)
+            .map_err(|_| invalid_data("Invalid private key".to_owned()))?;
+        let public = secp256k1::PublicKey::from_secret_key(&secret).serialize();
+        let address = Address::from_slice(&keccak256(&public[1..])[12..]);
+        Ok(Self { secret, address })
+    }
+
+    /// Decrypts a version 3 keystore, only the scrypt key derivation and the
+    /// aes-128-ctr cipher are supported
+    pub fn from_keystore(json: &str, password: &str) -> io::Result<Self> {
+        let keystore: Keystore = serde_json::from_str(json).map_err(|e| invalid_data(e.to_string()))?;
+        let crypto = keystore.crypto;
+        if crypto.kdf != "scrypt" || crypto.cipher != "aes-128-ctr" {
+            return Err(invalid_data(format!("Unsupported keystore: {} with {}", crypto.kdf, crypto.cipher)));
+        }
+
+        let params = &crypto.kdfparams;
+        if !params.n.is_power_of_two() || params.dklen < 32 {
+            return Err(invalid_data("Invalid scrypt parameters".to_owned()));
+        }
+        let log_n = params.n.trailing_zeros() as u8;
+        let params = scrypt::ScryptParams::new(log_n, params.r, params.p)
+            .map_err(|_| invalid_data("Invalid scrypt parameters".to_owned()))?;
+        let mut key = vec![0; crypto.kdfparams.dklen];
+        scrypt::scrypt(password.as_bytes(), &rpc::decode_hex(&crypto.kdfparams.salt)?, &params, &mut key)
+            .map_err(|_| invalid_data("Invalid scrypt parameters".to_owned()))?;
+
+        let mut secret = rpc::decode_hex(&crypto.ciphertext)?;
+        let mac = keccak256(&[&key[16..32], &secret[..]].concat());
+        if mac[..] != rpc::decode_hex(&crypto.mac)?[..] {
+            return Err(invalid_data("Wrong password".to_owned()));
+        }
+
+        let iv = rpc::decode_hex(&crypto.cipherparams.iv)?;
+        Aes128Ctr::new_var(&key[..16], &iv)
+            .map_err(|_| invalid_data("Invalid IV".to_owned()))?
+            .apply_keystream(&mut secret);
+        Self::from_secret(&secret)
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Signs the transaction for `chain_id`, the nonce, gas and gas price must
+    /// be set. Returns the raw transaction for `eth_sendRawTransaction`
+    pub fn sign_transaction(&self, transaction: &TransactionRequest, chain_id: u64) -> io::Result<Vec<u8>> {
+        let missing = |field: &str| invalid_input(format!("The {} of the transaction must be set to sign it", field));
+        let nonce = transaction.nonce.ok_or_else(|| missing("nonce"))?;
+        let gas = transaction.gas.ok_or_else(|| missing("gas"))?;
+        let gas_price = transaction.gas_price.ok_or_else(|| missing("gas price"))?;
+
+        let append_fields = |stream: &mut RlpStream| {
+            stream.append(&nonce);
+            stream.append(&gas_price);
+            stream.append(&gas);
+            match transaction.to {
+                Some(to) => stream.append(&to),
+                None => stream.append_empty_data(),
+            };
+            stream.append(&transaction.value.unwrap_or_default());
+            stream.append(&transaction.data);
+        };
+
+        let mut unsigned = RlpStream::new_list(9);
+        append_fields(&mut unsigned);
+        unsigned.append(&chain_id);
+        unsigned.append(&0u8);
+        unsigned.append(&0u8);
+        let hash = keccak256(&unsigned.out());
+
+        let (signature, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&hash), &self.secret);
+        let signature = signature.serialize();
+
+        let mut signed = RlpStream::new_list(9);
+        append_fields(&mut signed);
+        signed.append(&(u64::from(recovery_id.serialize()) + chain_id * 2 + 35));
+        signed.append(&U256::from_big_endian(&signature[..32]));
+        signed.append(&U256::from_big_endian(&signature[32..]));
+        Ok(signed.out())
+    }
+}
+
+/// Hash of a raw transaction, as returned by the node once it's sent
+pub fn transaction_hash(raw: &[u8]) -> H256 {
+    H256::from(keccak256(raw))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hex::{FromHex, ToHex};
+
+    /// Key `0x4646...46` encrypted with `tortilla`, with a cheap scrypt
+    const KEYSTORE: &str = r#"{
+        "address": "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+            "ciphertext": "9a35f0cd7a5f6df0a83844660a3c8bd0a73f1e67566fd4219deb901309f9be34",
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": 1024,
+                "p": 1,
+                "r": 8,
+                "salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+            },
+            "mac": "5dfd39c2f6288609a879c959138c9e81d3fcdbec7c46a2ac741fbe6dbfc8ed62"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    #[test]
+    fn test_keystore() {
+        let wallet = Wallet::from_keystore(KEYSTORE, "tortilla").unwrap();
+        assert_eq!(wallet.address(), "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap());
+
+        let err = Wallet::from_keystore(KEYSTORE, "tortila").unwrap_err();
+        assert_eq!(err.to_string(), "Wrong password");
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        // Example of EIP-155
+        let wallet = Wallet::from_secret(&"46".repeat(32).from_hex::<Vec<u8>>().unwrap()).unwrap();
+        let transaction = TransactionRequest {
+            to: Some("3535353535353535353535353535353535353535".parse().unwrap()),
+            nonce: Some(U256::from(9)),
+            gas_price: Some(U256::from(20_000_000_000u64)),
+            gas: Some(U256::from(21000)),
+            value: Some(U256::from(1_000_000_000_000_000_000u64)),
+            ..TransactionRequest::default()
+        };
+
+        let raw = wallet.sign_transaction(&transaction, 1).unwrap();
+        assert_eq!(
+            raw.to_hex::<String>(),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd9\
+             39bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b\
+             297fb1966a3b6d83"
+        );
+
+        let unsigned = TransactionRequest { nonce: None, ..transaction };
+        assert!(wallet.sign_transaction(&unsigned, 1).is_err());
+    }
+}
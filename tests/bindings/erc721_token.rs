@@ -0,0 +1,152 @@
+//! Bindings of the `ERC721Token` contract, generated by tortilla. Do not edit.
+#![allow(dead_code, unused_mut, unused_variables, clippy::all)]
+
+pub const NAME: &str = "ERC721Token";
+pub const ABI: &str = r##"[{"type":"constructor","inputs":[{"name":"proposalNames","type":"bytes32[]","components":null}],"stateMutability":"nonpayable"},{"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address","components":null}],"stateMutability":"view"},{"type":"function","name":"setCompleted","inputs":[{"name":"completed","type":"uint256","components":null}],"outputs":[],"stateMutability":"nonpayable"},{"type":"function","name":"safeTransferFrom","inputs":[{"name":"from","type":"address","components":null},{"name":"to","type":"address","components":null},{"name":"tokenId","type":"uint256","components":null}],"outputs":[],"stateMutability":"nonpayable"},{"type":"function","name":"safeTransferFrom","inputs":[{"name":"from","type":"address","components":null},{"name":"to","type":"address","components":null},{"name":"tokenId","type":"uint256","components":null},{"name":"data","type":"bytes","components":null}],"outputs":[],"stateMutability":"nonpayable"},{"type":"function","name":"get","inputs":[{"name":"index","type":"uint256","components":null}],"outputs":[{"name":"item","type":"tuple","components":[{"name":"owner","type":"address","components":null},{"name":"times","type":"uint64[2]","components":null}]},{"name":"","type":"string","components":null}],"stateMutability":"view"},{"type":"event","name":"Transfer","inputs":[{"name":"from","type":"address","components":null,"indexed":true},{"name":"to","type":"address","components":null,"indexed":true},{"name":"value","type":"uint256","components":null,"indexed":false}],"anonymous":false},{"type":"event","name":"Registered","inputs":[{"name":"name","type":"string","components":null,"indexed":true},{"name":"owner","type":"address","components":null,"indexed":false}],"anonymous":false}]"##;
+pub const BYTECODE: &str = "6080";
+
+pub fn bytecode() -> Vec<u8> {
+    (0..BYTECODE.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&BYTECODE[i..i + 2], 16).expect("Unlinked or invalid bytecode"))
+        .collect()
+}
+
+/// Creation data: the bytecode followed by the encoded constructor arguments
+pub fn deploy_data(proposal_names: Vec<[u8; 32]>) -> Vec<u8> {
+    [self::bytecode(), ethabi::encode(&[ethabi::Token::Array(proposal_names.into_iter().map(|v0| ethabi::Token::FixedBytes(v0.to_vec())).collect())])].concat()
+}
+
+pub mod functions {
+
+    /// `function owner() view returns (address)`
+    pub mod owner {
+        pub const SIGNATURE: &str = "owner()";
+        pub const SELECTOR: [u8; 4] = [0x8d, 0xa5, 0xcb, 0x5b];
+
+        pub fn encode_input() -> Vec<u8> {
+            [&SELECTOR[..], &ethabi::encode(&[])].concat()
+        }
+
+        pub fn decode_output(data: &[u8]) -> ethabi::Result<ethabi::Address> {
+            let mut tokens = ethabi::decode(&[ethabi::ParamType::Address], data)?.into_iter();
+            Ok(tokens.next().ok_or(ethabi::Error::InvalidData)?.to_address().ok_or(ethabi::Error::InvalidData)?)
+        }
+    }
+
+    /// `function setCompleted(uint256 completed)`
+    pub mod set_completed {
+        pub const SIGNATURE: &str = "setCompleted(uint256)";
+        pub const SELECTOR: [u8; 4] = [0xfd, 0xac, 0xd5, 0x76];
+
+        pub fn encode_input(completed: ethabi::Uint) -> Vec<u8> {
+            [&SELECTOR[..], &ethabi::encode(&[ethabi::Token::Uint(completed)])].concat()
+        }
+
+        pub fn decode_output(_data: &[u8]) -> ethabi::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// `function safeTransferFrom(address from, address to, uint256 tokenId)`
+    pub mod safe_transfer_from {
+        pub const SIGNATURE: &str = "safeTransferFrom(address,address,uint256)";
+        pub const SELECTOR: [u8; 4] = [0x42, 0x84, 0x2e, 0x0e];
+
+        pub fn encode_input(from: ethabi::Address, to: ethabi::Address, token_id: ethabi::Uint) -> Vec<u8> {
+            [&SELECTOR[..], &ethabi::encode(&[ethabi::Token::Address(from), ethabi::Token::Address(to), ethabi::Token::Uint(token_id)])].concat()
+        }
+
+        pub fn decode_output(_data: &[u8]) -> ethabi::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// `function safeTransferFrom(address from, address to, uint256 tokenId, bytes data)`
+    pub mod safe_transfer_from_1 {
+        pub const SIGNATURE: &str = "safeTransferFrom(address,address,uint256,bytes)";
+        pub const SELECTOR: [u8; 4] = [0xb8, 0x8d, 0x4f, 0xde];
+
+        pub fn encode_input(from: ethabi::Address, to: ethabi::Address, token_id: ethabi::Uint, data: Vec<u8>) -> Vec<u8> {
+            [&SELECTOR[..], &ethabi::encode(&[ethabi::Token::Address(from), ethabi::Token::Address(to), ethabi::Token::Uint(token_id), ethabi::Token::Bytes(data)])].concat()
+        }
+
+        pub fn decode_output(_data: &[u8]) -> ethabi::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// `function get(uint256 index) view returns (tuple(address owner, uint64[2] times) item, string)`
+    pub mod get {
+        pub const SIGNATURE: &str = "get(uint256)";
+        pub const SELECTOR: [u8; 4] = [0x95, 0x07, 0xd3, 0x9a];
+
+        pub fn encode_input(index: ethabi::Uint) -> Vec<u8> {
+            [&SELECTOR[..], &ethabi::encode(&[ethabi::Token::Uint(index)])].concat()
+        }
+
+        pub fn decode_output(data: &[u8]) -> ethabi::Result<((ethabi::Address, [ethabi::Uint; 2]), String)> {
+            let mut tokens = ethabi::decode(&[ethabi::ParamType::Tuple(vec![Box::new(ethabi::ParamType::Address), Box::new(ethabi::ParamType::FixedArray(Box::new(ethabi::ParamType::Uint(64)), 2))]), ethabi::ParamType::String], data)?.into_iter();
+            Ok((match tokens.next().ok_or(ethabi::Error::InvalidData)? { ethabi::Token::Tuple(v0) => { let mut v0 = v0.into_iter(); (v0.next().ok_or(ethabi::Error::InvalidData)?.to_address().ok_or(ethabi::Error::InvalidData)?, <[ethabi::Uint; 2] as std::convert::TryFrom<Vec<ethabi::Uint>>>::try_from(v0.next().ok_or(ethabi::Error::InvalidData)?.to_fixed_array().ok_or(ethabi::Error::InvalidData)?.into_iter().map(|v1| Ok(v1.to_uint().ok_or(ethabi::Error::InvalidData)?)).collect::<ethabi::Result<Vec<ethabi::Uint>>>()?).map_err(|_| ethabi::Error::InvalidData)?,) }, _ => return Err(ethabi::Error::InvalidData) }, tokens.next().ok_or(ethabi::Error::InvalidData)?.to_string().ok_or(ethabi::Error::InvalidData)?))
+        }
+    }
+}
+
+pub mod events {
+
+    /// `event Transfer(address indexed from, address indexed to, uint256 value)`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Transfer {
+        pub from: ethabi::Address,
+        pub to: ethabi::Address,
+        pub value: ethabi::Uint,
+    }
+
+    impl Transfer {
+        pub const SIGNATURE: &str = "Transfer(address,address,uint256)";
+        pub const TOPIC: [u8; 32] = [0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa, 0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef];
+
+        pub fn decode(topics: &[ethabi::Hash], data: &[u8]) -> ethabi::Result<Self> {
+            let topics = match topics.split_first() {
+                Some((topic, topics)) if topic.as_bytes() == &Self::TOPIC[..] => topics,
+                _ => return Err(ethabi::Error::InvalidData),
+            };
+            if topics.len() != 2 {
+                return Err(ethabi::Error::InvalidData);
+            }
+            let mut data = ethabi::decode(&[ethabi::ParamType::Uint(256)], data)?.into_iter();
+            Ok(Self {
+                from: ethabi::decode(&[ethabi::ParamType::Address], topics[0].as_bytes())?.remove(0).to_address().ok_or(ethabi::Error::InvalidData)?,
+                to: ethabi::decode(&[ethabi::ParamType::Address], topics[1].as_bytes())?.remove(0).to_address().ok_or(ethabi::Error::InvalidData)?,
+                value: data.next().ok_or(ethabi::Error::InvalidData)?.to_uint().ok_or(ethabi::Error::InvalidData)?,
+            })
+        }
+    }
+
+    /// `event Registered(string indexed name, address owner)`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Registered {
+        pub name: ethabi::Hash,
+        pub owner: ethabi::Address,
+    }
+
+    impl Registered {
+        pub const SIGNATURE: &str = "Registered(string,address)";
+        pub const TOPIC: [u8; 32] = [0x50, 0xf7, 0x4c, 0xa4, 0x5c, 0xaa, 0xc8, 0x02, 0x0b, 0x8d, 0x89, 0x1b, 0xd1, 0x3e, 0xa5, 0xa2, 0xd7, 0x95, 0x64, 0x98, 0x6e, 0xe6, 0xa8, 0x39, 0xf0, 0xd9, 0x14, 0x89, 0x63, 0x88, 0x32, 0x2d];
+
+        pub fn decode(topics: &[ethabi::Hash], data: &[u8]) -> ethabi::Result<Self> {
+            let topics = match topics.split_first() {
+                Some((topic, topics)) if topic.as_bytes() == &Self::TOPIC[..] => topics,
+                _ => return Err(ethabi::Error::InvalidData),
+            };
+            if topics.len() != 1 {
+                return Err(ethabi::Error::InvalidData);
+            }
+            let mut data = ethabi::decode(&[ethabi::ParamType::Address], data)?.into_iter();
+            Ok(Self {
+                name: topics[0],
+                owner: data.next().ok_or(ethabi::Error::InvalidData)?.to_address().ok_or(ethabi::Error::InvalidData)?,
+            })
+        }
+    }
+}